@@ -0,0 +1,88 @@
+#!/usr/bin/env python3
+"""Generate esv-core's emoji property tables from the Unicode Character Database.
+
+Usage:
+    curl -O https://www.unicode.org/Public/17.0.0/ucd/emoji/emoji-data.txt
+    python3 scripts/generate_emoji_tables.py emoji-data.txt 17.0.0 > src/core/src/emoji_tables.rs
+    cargo fmt --all
+
+When bumping the Unicode version, keep it in step with the `unicode-segmentation`
+dependency so separator validation and grapheme segmentation agree.
+"""
+
+import sys
+
+PROPERTIES = [
+    ("Emoji", "EMOJI"),
+    ("Emoji_Presentation", "EMOJI_PRESENTATION"),
+    ("Emoji_Modifier_Base", "EMOJI_MODIFIER_BASE"),
+]
+
+
+def load_ranges(path):
+    ranges = {name: [] for name, _ in PROPERTIES}
+    with open(path, encoding="utf-8") as f:
+        for line in f:
+            line = line.split("#", 1)[0].strip()
+            if not line:
+                continue
+            code_points, prop = (part.strip() for part in line.split(";"))
+            if prop not in ranges:
+                continue
+            lo, _, hi = code_points.partition("..")
+            ranges[prop].append((int(lo, 16), int(hi or lo, 16)))
+    return {prop: merge(rs) for prop, rs in ranges.items()}
+
+
+def merge(ranges):
+    merged = []
+    for lo, hi in sorted(ranges):
+        if merged and lo <= merged[-1][1] + 1:
+            merged[-1] = (merged[-1][0], max(hi, merged[-1][1]))
+        else:
+            merged.append((lo, hi))
+    return merged
+
+
+def main():
+    path, version = sys.argv[1], sys.argv[2]
+    major, minor, patch = (int(part) for part in version.split("."))
+    ranges = load_ranges(path)
+
+    out = sys.stdout
+    out.write("//! Emoji property tables from the Unicode Character Database\n")
+    out.write("//!\n")
+    out.write("//! DO NOT EDIT: generated by `scripts/generate_emoji_tables.py` from\n")
+    out.write(f"//! `emoji-data.txt` for Unicode {version}.\n\n")
+    out.write("/// Version of the Unicode emoji data backing `is_emoji` and `validate_separator`\n")
+    out.write(f"pub const UNICODE_VERSION: (u8, u8, u8) = ({major}, {minor}, {patch});\n")
+
+    for prop, const in PROPERTIES:
+        out.write(f"\n/// Code point ranges with `{prop}=Yes`\n")
+        out.write(f"pub(crate) const {const}: &[(char, char)] = &[\n")
+        for lo, hi in ranges[prop]:
+            out.write(f"    ('\\u{{{lo:X}}}', '\\u{{{hi:X}}}'),\n")
+        out.write("];\n")
+
+    out.write(
+        """
+/// Check if a character falls within one of the ranges of a property table
+pub(crate) fn contains(table: &[(char, char)], c: char) -> bool {
+    table
+        .binary_search_by(|&(lo, hi)| {
+            if c < lo {
+                std::cmp::Ordering::Greater
+            } else if c > hi {
+                std::cmp::Ordering::Less
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+"""
+    )
+
+
+if __name__ == "__main__":
+    main()
@@ -8,7 +8,20 @@ use std::io::{self, Read, Write};
 
 use anyhow::{Context, Result};
 use clap::{Args, Parser, Subcommand, ValueEnum};
-use esv_core::{EsvDocument, EsvParser, EsvSerializer, LineEnding, DEFAULT_SEPARATOR};
+use esv_core::{
+    EscapeStyle, EsvDialect, EsvDocument, EsvError, EsvParser, EsvSerializer, EsvSniffer,
+    EsvWarning, HeaderPolicy, LineEnding, RaggedPolicy, DEFAULT_SEPARATOR, UNICODE_VERSION,
+};
+
+/// `--separator` value asking the CLI to detect the separator from the input
+const AUTO_SEPARATOR: &str = "auto";
+
+/// Exit code of `esv validate` for invalid input
+pub const EXIT_INVALID: i32 = 1;
+
+/// Exit code of `esv validate` for valid input with warnings (2 is taken by usage
+/// errors)
+pub const EXIT_WARNINGS: i32 = 3;
 
 /// ESV (Emoji Separated Values) command-line tool
 #[derive(Parser, Debug)]
@@ -34,6 +47,8 @@ pub enum Commands {
     Info(InfoArgs),
 }
 
+// Each flag is an independent command-line switch
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Args, Debug)]
 pub struct ParseArgs {
     /// Input file (use - for stdin)
@@ -48,17 +63,64 @@ pub struct ParseArgs {
     #[arg(short = 'H', long)]
     pub headers: bool,
 
-    /// Custom emoji separator
+    /// Custom emoji separator (a single emoji or emoji sequence), or `auto` to detect
+    /// the separator and header row from the input
     #[arg(short, long)]
-    pub separator: Option<char>,
+    pub separator: Option<String>,
 
     /// Enable strict field count validation
     #[arg(long)]
     pub strict: bool,
 
+    /// Reject double quotes inside unquoted fields
+    #[arg(long)]
+    pub strict_syntax: bool,
+
+    /// Keep CR and CRLF line breaks inside quoted fields instead of normalizing them
+    /// to LF
+    #[arg(long)]
+    pub preserve_line_endings: bool,
+
+    /// How duplicate, empty and whitespace-padded header names are handled
+    #[arg(long, value_enum, default_value = "accept")]
+    pub header_policy: HeaderPolicyArg,
+
+    /// How records with more or fewer fields than the header (or first) row are
+    /// handled
+    #[arg(long, value_enum, default_value = "keep")]
+    pub ragged: RaggedArg,
+
+    /// Field value used by `--ragged pad` (default: empty)
+    #[arg(long, value_name = "TEXT", default_value = "")]
+    pub ragged_filler: String,
+
+    /// Character that encloses quoted fields (default: ")
+    #[arg(long, value_name = "CHAR")]
+    pub quote: Option<char>,
+
+    /// How quotes are escaped inside quoted fields (default: doubled)
+    #[arg(long, value_enum)]
+    pub escape: Option<EscapeArg>,
+
+    /// Skip lines starting with this prefix (such as `#`) as comments
+    #[arg(long, value_name = "PREFIX")]
+    pub comment_prefix: Option<String>,
+
+    /// Skip empty lines between records
+    #[arg(long)]
+    pub skip_blank_lines: bool,
+
     /// Output format
     #[arg(short, long, value_enum, default_value = "json")]
     pub format: OutputFormat,
+
+    /// Replace invalid UTF-8 with U+FFFD instead of failing
+    #[arg(long)]
+    pub lossy_utf8: bool,
+
+    /// Dialect config file supplying defaults for the other settings
+    #[arg(long, value_name = "FILE")]
+    pub dialect: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -71,36 +133,101 @@ pub struct SerializeArgs {
     #[arg(short, long, default_value = "-")]
     pub output: String,
 
-    /// Custom emoji separator
+    /// Custom emoji separator (a single emoji or emoji sequence)
     #[arg(short, long)]
-    pub separator: Option<char>,
+    pub separator: Option<String>,
 
     /// Always quote all fields
     #[arg(long)]
     pub always_quote: bool,
 
-    /// Line ending style
-    #[arg(long, value_enum, default_value = "lf")]
-    pub line_ending: LineEndingArg,
+    /// Start the output with a `#!esv` pragma line declaring its dialect
+    #[arg(long)]
+    pub pragma: bool,
+
+    /// Start the output with a comment block (one comment line per line of text)
+    #[arg(long, value_name = "TEXT")]
+    pub comment: Option<String>,
+
+    /// Comment prefix of the parsers reading the output: records starting with it
+    /// are quoted, and `--comment` lines are written behind it (default: #)
+    #[arg(long, value_name = "PREFIX")]
+    pub comment_prefix: Option<String>,
+
+    /// Character that encloses quoted fields (default: ")
+    #[arg(long, value_name = "CHAR")]
+    pub quote: Option<char>,
+
+    /// How quotes are escaped inside quoted fields (default: doubled)
+    #[arg(long, value_enum)]
+    pub escape: Option<EscapeArg>,
+
+    /// Line ending style (default: lf)
+    #[arg(long, value_enum)]
+    pub line_ending: Option<LineEndingArg>,
+
+    /// Dialect config file supplying defaults for the other settings
+    #[arg(long, value_name = "FILE")]
+    pub dialect: Option<String>,
 }
 
+// Each flag is an independent command-line switch
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Args, Debug)]
 pub struct ValidateArgs {
     /// Input file (use - for stdin)
     #[arg(default_value = "-")]
     pub input: String,
 
-    /// Custom emoji separator
+    /// Custom emoji separator (a single emoji or emoji sequence), or `auto` to detect
+    /// the separator and header row from the input
     #[arg(short, long)]
-    pub separator: Option<char>,
+    pub separator: Option<String>,
 
     /// Enable strict field count validation
     #[arg(long)]
     pub strict: bool,
 
+    /// Reject double quotes inside unquoted fields
+    #[arg(long)]
+    pub strict_syntax: bool,
+
     /// Treat first row as headers
     #[arg(short = 'H', long)]
     pub headers: bool,
+
+    /// Replace invalid UTF-8 with U+FFFD instead of failing
+    #[arg(long)]
+    pub lossy_utf8: bool,
+
+    /// Dialect config file supplying defaults for the other settings
+    #[arg(long, value_name = "FILE")]
+    pub dialect: Option<String>,
+
+    /// Character that encloses quoted fields (default: ")
+    #[arg(long, value_name = "CHAR")]
+    pub quote: Option<char>,
+
+    /// How quotes are escaped inside quoted fields (default: doubled)
+    #[arg(long, value_enum)]
+    pub escape: Option<EscapeArg>,
+
+    /// Skip lines starting with this prefix (such as `#`) as comments
+    #[arg(long, value_name = "PREFIX")]
+    pub comment_prefix: Option<String>,
+
+    /// Skip empty lines between records
+    #[arg(long)]
+    pub skip_blank_lines: bool,
+
+    /// How duplicate, empty and whitespace-padded header names are handled (default:
+    /// report them as warnings)
+    #[arg(long, value_enum)]
+    pub header_policy: Option<HeaderPolicyArg>,
+
+    /// List at most this many errors (default: all)
+    #[arg(long, value_name = "N")]
+    pub max_errors: Option<usize>,
 }
 
 #[derive(Args, Debug)]
@@ -112,6 +239,14 @@ pub struct InfoArgs {
     /// Show format specification
     #[arg(long)]
     pub spec: bool,
+
+    /// Show the Unicode version of the emoji data used to validate separators
+    #[arg(long)]
+    pub unicode: bool,
+
+    /// Show the settings of a dialect config file, with defaults filled in
+    #[arg(long, value_name = "FILE")]
+    pub dialect: Option<String>,
 }
 
 #[derive(ValueEnum, Clone, Debug, Default)]
@@ -133,6 +268,69 @@ pub enum LineEndingArg {
     Crlf,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum HeaderPolicyArg {
+    /// Keep header names as they are
+    #[default]
+    Accept,
+    /// Report bad header names as errors
+    Reject,
+    /// Trim names, name empty headers `column_N` and suffix duplicates (`name_2`)
+    Rename,
+}
+
+impl From<HeaderPolicyArg> for HeaderPolicy {
+    fn from(arg: HeaderPolicyArg) -> Self {
+        match arg {
+            HeaderPolicyArg::Accept => HeaderPolicy::Accept,
+            HeaderPolicyArg::Reject => HeaderPolicy::Reject,
+            HeaderPolicyArg::Rename => HeaderPolicy::Rename,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum EscapeArg {
+    /// Double the quote (`""`), as in RFC 4180
+    #[default]
+    Doubled,
+    /// Backslash escapes (`\"`, `\\`, `\n`, `\r`)
+    Backslash,
+}
+
+impl From<EscapeArg> for EscapeStyle {
+    fn from(arg: EscapeArg) -> Self {
+        match arg {
+            EscapeArg::Doubled => EscapeStyle::Doubled,
+            EscapeArg::Backslash => EscapeStyle::Backslash,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum RaggedArg {
+    /// Keep records as they are
+    #[default]
+    Keep,
+    /// Pad short records with `--ragged-filler`
+    Pad,
+    /// Drop extra fields
+    Truncate,
+    /// Fail on the first ragged record
+    Error,
+}
+
+impl RaggedArg {
+    fn policy(self, filler: &str) -> RaggedPolicy {
+        match self {
+            RaggedArg::Keep => RaggedPolicy::Keep,
+            RaggedArg::Pad => RaggedPolicy::Pad(filler.to_string()),
+            RaggedArg::Truncate => RaggedPolicy::Truncate,
+            RaggedArg::Error => RaggedPolicy::Error,
+        }
+    }
+}
+
 impl Cli {
     /// Run the CLI application
     ///
@@ -142,35 +340,45 @@ impl Cli {
     /// - Input file cannot be read
     /// - Output file cannot be written
     /// - ESV parsing fails
+    /// - ESV serialization fails (invalid separator or quote character)
     /// - JSON parsing fails
+    /// - A dialect file cannot be read or parsed
     pub fn run(&self) -> Result<()> {
         match &self.command {
             Commands::Parse(args) => run_parse(args),
             Commands::Serialize(args) => run_serialize(args),
             Commands::Validate(args) => run_validate(args),
-            Commands::Info(args) => {
-                run_info(args);
-                Ok(())
-            }
+            Commands::Info(args) => run_info(args),
         }
     }
 }
 
 fn run_parse(args: &ParseArgs) -> Result<()> {
-    let input = read_input(&args.input)?;
-
-    let mut parser = EsvParser::new();
-    if let Some(sep) = args.separator {
-        parser = parser.with_separator(sep);
-    }
-    if args.headers {
-        parser = parser.with_headers(true);
-    }
-    if args.strict {
-        parser = parser.with_strict_field_count(true);
-    }
-
-    let doc = parser.parse(&input).context("Failed to parse ESV input")?;
+    let input = read_input_bytes(&args.input)?;
+
+    let mut dialect = load_dialect(args.dialect.as_deref())?;
+    apply_quoting(&mut dialect, args.quote, args.escape);
+    apply_comments(
+        &mut dialect,
+        args.comment_prefix.as_deref(),
+        args.skip_blank_lines,
+    );
+    let parser = build_parser(
+        &String::from_utf8_lossy(&input),
+        dialect,
+        args.separator.as_deref(),
+        args.headers,
+        args.strict,
+    )
+    .with_lossy_utf8(args.lossy_utf8)
+    .with_strict_syntax(args.strict_syntax)
+    .with_preserve_line_endings(args.preserve_line_endings)
+    .with_header_policy(args.header_policy.into())
+    .with_ragged_policy(args.ragged.policy(&args.ragged_filler));
+
+    let doc = parser
+        .parse_bytes(&input)
+        .context("Failed to parse ESV input")?;
 
     let output = match args.format {
         OutputFormat::Json => format_as_json(&doc, false)?,
@@ -187,63 +395,131 @@ fn run_serialize(args: &SerializeArgs) -> Result<()> {
 
     let doc: EsvDocument = parse_json_input(&input)?;
 
-    let mut serializer = EsvSerializer::new();
-    if let Some(sep) = args.separator {
-        serializer = serializer.with_separator(sep);
+    let mut dialect = load_dialect(args.dialect.as_deref())?;
+    apply_quoting(&mut dialect, args.quote, args.escape);
+    apply_comments(&mut dialect, args.comment_prefix.as_deref(), false);
+    let mut serializer = EsvSerializer::from_dialect(&dialect);
+    if let Some(sep) = &args.separator {
+        serializer = serializer.with_separator(sep.as_str());
     }
     if args.always_quote {
         serializer = serializer.with_always_quote(true);
     }
-    serializer = serializer.with_line_ending(match args.line_ending {
-        LineEndingArg::Lf => LineEnding::Lf,
-        LineEndingArg::Crlf => LineEnding::Crlf,
-    });
+    if args.pragma {
+        serializer = serializer.with_pragma(true);
+    }
+    if let Some(comment) = &args.comment {
+        serializer = serializer.with_comment(comment.as_str());
+    }
+    if let Some(line_ending) = &args.line_ending {
+        serializer = serializer.with_line_ending(match line_ending {
+            LineEndingArg::Lf => LineEnding::Lf,
+            LineEndingArg::Crlf => LineEnding::Crlf,
+        });
+    }
 
-    let output = serializer.serialize(&doc);
+    let output = serializer
+        .try_serialize(&doc)
+        .context("Failed to serialize ESV output")?;
     write_output(&args.output, &output)?;
     Ok(())
 }
 
 fn run_validate(args: &ValidateArgs) -> Result<()> {
-    let input = read_input(&args.input)?;
-
-    let mut parser = EsvParser::new();
-    if let Some(sep) = args.separator {
-        parser = parser.with_separator(sep);
-    }
-    if args.headers {
-        parser = parser.with_headers(true);
-    }
-    if args.strict {
-        parser = parser.with_strict_field_count(true);
-    }
+    let input = read_input_bytes(&args.input)?;
+    // Error spans index the decoded text, which matches this up to any invalid UTF-8
+    let source = String::from_utf8_lossy(&input);
+
+    let mut dialect = load_dialect(args.dialect.as_deref())?;
+    apply_quoting(&mut dialect, args.quote, args.escape);
+    apply_comments(
+        &mut dialect,
+        args.comment_prefix.as_deref(),
+        args.skip_blank_lines,
+    );
+    let parser = build_parser(
+        &source,
+        dialect,
+        args.separator.as_deref(),
+        args.headers,
+        args.strict,
+    )
+    .with_lossy_utf8(args.lossy_utf8)
+    .with_strict_syntax(args.strict_syntax)
+    // Without a policy, bad header names are found by rejecting them, then reported
+    // as warnings
+    .with_header_policy(args.header_policy.map_or(HeaderPolicy::Reject, Into::into));
+
+    let report = parser
+        .decode_utf8(&input)
+        .and_then(|text| parser.parse_lenient(&text));
+    let (report, header_warnings) = match report {
+        Ok(mut report) if args.header_policy.is_none() => {
+            let header_warnings = take_header_errors(&mut report.errors);
+            (Ok(report), header_warnings)
+        }
+        report => (report, Vec::new()),
+    };
 
-    match parser.parse(&input) {
-        Ok(doc) => {
+    match report {
+        Ok(report) if report.is_valid() => {
+            let doc = &report.document;
             let record_count = doc.len();
             let field_count = doc.field_count().unwrap_or(0);
             let has_headers = doc.headers.is_some();
 
-            println!("✅ Valid ESV");
+            let has_warnings = !report.warnings.is_empty() || !header_warnings.is_empty();
+            if has_warnings {
+                println!("⚠️ Valid ESV with warnings");
+            } else {
+                println!("✅ Valid ESV");
+            }
+            if args.separator.as_deref() == Some(AUTO_SEPARATOR) {
+                println!("   Detected separator: {}", parser.separator());
+            }
             println!("   Records: {record_count}");
             println!("   Fields per record: {field_count}");
             println!("   Has headers: {has_headers}");
+            if has_warnings {
+                eprint!(
+                    "{}",
+                    format_warnings(&header_warnings, &report.warnings, &source)
+                );
+                std::process::exit(EXIT_WARNINGS);
+            }
             Ok(())
         }
+        Ok(report) => {
+            eprint!(
+                "{}",
+                format_errors(&report.errors, &source, args.max_errors)
+            );
+            std::process::exit(EXIT_INVALID);
+        }
         Err(e) => {
-            eprintln!("❌ Invalid ESV: {e}");
-            std::process::exit(1);
+            eprint!("{}", format_errors(&[e], &source, None));
+            std::process::exit(EXIT_INVALID);
         }
     }
 }
 
-fn run_info(args: &InfoArgs) {
+fn run_info(args: &InfoArgs) -> Result<()> {
+    if let Some(path) = &args.dialect {
+        print!("{}", load_dialect(Some(path))?);
+        return Ok(());
+    }
+
     if args.separator {
         println!(
             "Default separator: {DEFAULT_SEPARATOR} (U+{:04X})",
             DEFAULT_SEPARATOR as u32
         );
-        return;
+        return Ok(());
+    }
+
+    if args.unicode {
+        println!("Emoji data: Unicode {}", unicode_version());
+        return Ok(());
     }
 
     if args.spec {
@@ -260,7 +536,7 @@ fn run_info(args: &InfoArgs) {
         println!("6. Fields containing line breaks, quotes, or the separator");
         println!("   should be enclosed in double quotes.");
         println!("7. Double quotes inside a field are escaped by doubling (\"\").");
-        return;
+        return Ok(());
     }
 
     // Default: show both
@@ -269,13 +545,92 @@ fn run_info(args: &InfoArgs) {
         "Default separator: {DEFAULT_SEPARATOR} (U+{:04X})",
         DEFAULT_SEPARATOR as u32
     );
+    println!("Emoji data: Unicode {}", unicode_version());
     println!();
     println!("Use --spec for format specification");
     println!("Use --help for available commands");
+    Ok(())
 }
 
 // Helper functions
 
+/// Build a parser from a dialect and the shared parse/validate flags
+///
+/// Flags override the dialect. With `--separator auto` the separator and header
+/// presence are sniffed from the input; `-H` still forces a header row.
+fn build_parser(
+    input: &str,
+    mut dialect: EsvDialect,
+    separator: Option<&str>,
+    headers: bool,
+    strict: bool,
+) -> EsvParser {
+    match separator {
+        Some(AUTO_SEPARATOR) => {
+            let sniffed = EsvSniffer::new()
+                .with_quote(dialect.quote)
+                .sniff(input)
+                .dialect();
+            // Keep the dialect's policy unless it would reject the detected separator
+            if dialect
+                .separator_policy
+                .validate(&sniffed.separator)
+                .is_err()
+            {
+                dialect.separator_policy = sniffed.separator_policy;
+            }
+            dialect.separator = sniffed.separator;
+            dialect.has_headers = sniffed.has_headers;
+        }
+        Some(sep) => dialect.separator = sep.to_string(),
+        None => {}
+    }
+    if headers {
+        dialect.has_headers = true;
+    }
+    if strict {
+        dialect.strict_field_count = true;
+    }
+    dialect.parser()
+}
+
+/// Override the dialect's quote character and escape style with the `--quote` and
+/// `--escape` flags, if given
+fn apply_quoting(dialect: &mut EsvDialect, quote: Option<char>, escape: Option<EscapeArg>) {
+    if let Some(quote) = quote {
+        dialect.quote = quote;
+    }
+    if let Some(escape) = escape {
+        dialect.escape_style = escape.into();
+    }
+}
+
+/// Override the dialect's comment prefix with `--comment-prefix` (an empty prefix
+/// disables comments), and turn on blank-line skipping with `--skip-blank-lines`
+fn apply_comments(dialect: &mut EsvDialect, prefix: Option<&str>, skip_blank_lines: bool) {
+    if let Some(prefix) = prefix {
+        dialect.comment_prefix = Some(prefix.to_string()).filter(|p| !p.is_empty());
+    }
+    if skip_blank_lines {
+        dialect.skip_blank_lines = true;
+    }
+}
+
+/// Load the `--dialect` config file, or the default dialect if none was given
+fn load_dialect(path: Option<&str>) -> Result<EsvDialect> {
+    let Some(path) = path else {
+        return Ok(EsvDialect::new());
+    };
+    let config =
+        fs::read_to_string(path).with_context(|| format!("Failed to read dialect file: {path}"))?;
+    EsvDialect::parse(&config).with_context(|| format!("Invalid dialect file: {path}"))
+}
+
+fn unicode_version() -> String {
+    let (major, minor, patch) = UNICODE_VERSION;
+    format!("{major}.{minor}.{patch}")
+}
+
 fn read_input(path: &str) -> Result<String> {
     if path == "-" {
         let mut buffer = String::new();
@@ -288,6 +643,19 @@ fn read_input(path: &str) -> Result<String> {
     }
 }
 
+/// Read raw input bytes, leaving UTF-8 decoding to the ESV parser
+fn read_input_bytes(path: &str) -> Result<Vec<u8>> {
+    if path == "-" {
+        let mut buffer = Vec::new();
+        io::stdin()
+            .read_to_end(&mut buffer)
+            .context("Failed to read from stdin")?;
+        Ok(buffer)
+    } else {
+        fs::read(path).with_context(|| format!("Failed to read file: {path}"))
+    }
+}
+
 fn write_output(path: &str, content: &str) -> Result<()> {
     if path == "-" {
         io::stdout()
@@ -347,6 +715,59 @@ fn format_as_text(doc: &EsvDocument) -> String {
     output
 }
 
+/// Format validation errors with snippets of `source`, listing at most `max_errors`
+/// of them
+fn format_errors(errors: &[EsvError], source: &str, max_errors: Option<usize>) -> String {
+    use std::fmt::Write;
+
+    let render = |error: &EsvError| error.render(source).replace('\n', "\n   ");
+    if let [error] = errors {
+        return format!("❌ Invalid ESV: {}\n", render(error));
+    }
+
+    let mut output = format!("❌ Invalid ESV: {} errors\n", errors.len());
+    let shown = max_errors.unwrap_or(errors.len()).min(errors.len());
+    for error in &errors[..shown] {
+        let _ = writeln!(output, "   {}", render(error));
+    }
+    if shown < errors.len() {
+        let _ = writeln!(output, "   ... and {} more", errors.len() - shown);
+    }
+    output
+}
+
+/// Remove duplicate, empty and whitespace-padded header errors from `errors` and
+/// return them
+fn take_header_errors(errors: &mut Vec<EsvError>) -> Vec<EsvError> {
+    let (header_errors, rest) = std::mem::take(errors).into_iter().partition(|e| {
+        matches!(
+            e,
+            EsvError::EmptyHeader { .. }
+                | EsvError::HeaderWhitespace { .. }
+                | EsvError::DuplicateHeader { .. }
+        )
+    });
+    *errors = rest;
+    header_errors
+}
+
+/// Format header problems reported as warnings, followed by parser warnings
+fn format_warnings(header_errors: &[EsvError], warnings: &[EsvWarning], source: &str) -> String {
+    use std::fmt::Write;
+
+    let count = header_errors.len() + warnings.len();
+    let noun = if count == 1 { "warning" } else { "warnings" };
+    let mut output = format!("⚠️ {count} {noun}\n");
+    let rendered = header_errors
+        .iter()
+        .map(|e| e.render(source))
+        .chain(warnings.iter().map(|w| w.render(source)));
+    for warning in rendered {
+        let _ = writeln!(output, "   {}", warning.replace('\n', "\n   "));
+    }
+    output
+}
+
 fn parse_json_input(input: &str) -> Result<EsvDocument> {
     #[derive(serde::Deserialize)]
     struct JsonInput {
@@ -383,6 +804,140 @@ mod tests {
         assert_eq!(json, r#"{"headers":["x","y"],"records":[["1","2"]]}"#);
     }
 
+    #[test]
+    fn test_build_parser_auto_separator() {
+        let input = "name⭐age\nAlice⭐30\nBob⭐25\n";
+        let doc = build_parser(input, EsvDialect::new(), Some(AUTO_SEPARATOR), false, false)
+            .parse(input)
+            .unwrap();
+        assert_eq!(
+            doc.headers,
+            Some(vec!["name".to_string(), "age".to_string()])
+        );
+        assert_eq!(doc.records.len(), 2);
+    }
+
+    #[test]
+    fn test_build_parser_flags_override_dialect() {
+        let dialect = EsvDialect::parse("separator = ⭐\nstrict = true\n").unwrap();
+        let input = "a🌟b\nc";
+        assert!(build_parser(input, dialect.clone(), None, false, false)
+            .parse("a⭐b\nc")
+            .is_err());
+
+        let doc = build_parser(input, dialect, Some("🌟"), true, false)
+            .parse("x🌟y\n1🌟2")
+            .unwrap();
+        assert_eq!(doc.headers, Some(vec!["x".to_string(), "y".to_string()]));
+        assert_eq!(doc.records, vec![vec!["1", "2"]]);
+    }
+
+    #[test]
+    fn test_quoting_flags_override_dialect() {
+        let mut dialect = EsvDialect::parse("quote = '\nescape = backslash\n").unwrap();
+        apply_quoting(&mut dialect, None, None);
+        assert_eq!(
+            (dialect.quote, dialect.escape_style),
+            ('\'', EscapeStyle::Backslash)
+        );
+
+        apply_quoting(&mut dialect, Some('`'), Some(EscapeArg::Doubled));
+        assert_eq!(
+            (dialect.quote, dialect.escape_style),
+            ('`', EscapeStyle::Doubled)
+        );
+    }
+
+    #[test]
+    fn test_comment_flags_override_dialect() {
+        let mut dialect =
+            EsvDialect::parse("comment_prefix = //\nskip_blank_lines = true").unwrap();
+        apply_comments(&mut dialect, None, false);
+        assert_eq!(dialect.comment_prefix.as_deref(), Some("//"));
+        assert!(dialect.skip_blank_lines);
+
+        apply_comments(&mut dialect, Some(""), false);
+        assert_eq!(dialect.comment_prefix, None);
+        apply_comments(&mut dialect, Some("#"), true);
+        assert_eq!(dialect.comment_prefix.as_deref(), Some("#"));
+    }
+
+    #[test]
+    fn test_format_errors() {
+        let errors = vec![
+            EsvError::EmptyInput,
+            EsvError::MissingPragma,
+            EsvError::EmptyInput,
+        ];
+        assert_eq!(
+            format_errors(&errors[..1], "", None),
+            "❌ Invalid ESV: empty input\n"
+        );
+        assert_eq!(
+            format_errors(&errors, "", None),
+            "❌ Invalid ESV: 3 errors\n   empty input\n   \
+             missing required '#!esv' pragma line\n   empty input\n"
+        );
+        assert_eq!(
+            format_errors(&errors, "", Some(1)),
+            "❌ Invalid ESV: 3 errors\n   empty input\n   ... and 2 more\n"
+        );
+    }
+
+    #[test]
+    fn test_format_errors_snippets() {
+        let source = "a🔥b\nc🔥\"d\"x\n";
+        let errors = EsvParser::new().parse_lenient(source).unwrap().errors;
+        assert_eq!(
+            format_errors(&errors, source, None),
+            "❌ Invalid ESV: unexpected character 'x' after closing quote at line 2, column 6
+    --> line 2, grapheme column 6, bytes 15..16
+     |
+   2 | c🔥\"d\"x
+     |       ^
+"
+        );
+    }
+
+    #[test]
+    fn test_format_warnings() {
+        let source = "a🔥b\"\n";
+        let warnings = EsvParser::new().parse_lenient(source).unwrap().warnings;
+        assert_eq!(
+            format_warnings(&[], &warnings, source),
+            "⚠️ 1 warning
+   bare quote in unquoted field at line 1, column 4
+    --> line 1, grapheme column 4, bytes 6..7
+     |
+   1 | a🔥b\"
+     |     ^
+"
+        );
+    }
+
+    #[test]
+    fn test_header_errors_as_warnings() {
+        let source = "id🔥id\n\"1\"x🔥2\n";
+        let parser = EsvParser::new()
+            .with_headers(true)
+            .with_header_policy(HeaderPolicy::Reject);
+        let mut report = parser.parse_lenient(source).unwrap();
+        let header_errors = take_header_errors(&mut report.errors);
+        assert!(matches!(
+            header_errors[..],
+            [EsvError::DuplicateHeader { field: 2, .. }]
+        ));
+        assert!(matches!(
+            report.errors[..],
+            [EsvError::UnexpectedCharAfterQuote { line: 2, .. }]
+        ));
+        assert!(
+            format_warnings(&header_errors, &report.warnings, source).starts_with(
+                "⚠️ 1 warning\n   duplicate header 'id' in field 2 at line 1 (first in field 1)\n"
+            )
+        );
+    }
+
     #[test]
     fn test_parse_json_input() {
         let input = r#"{"records":[["a","b"],["c","d"]]}"#;
@@ -1,7 +1,9 @@
 //! Benchmarks for ESV parsing and serialization
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
-use esv_core::{parse, parse_with_headers, serialize, EsvDocument, EsvParser, EsvSerializer};
+use esv_core::{
+    parse, parse_with_headers, serialize, EsvDocument, EsvParser, EsvReader, EsvSerializer,
+};
 
 fn generate_simple_esv(rows: usize, cols: usize) -> String {
     let mut result = String::new();
@@ -78,6 +80,22 @@ fn generate_esv_with_newlines(rows: usize, cols: usize) -> String {
     result
 }
 
+fn generate_long_fields(rows: usize, field_len: usize, quoted: bool, text: &str) -> String {
+    let field: String = text.chars().cycle().take(field_len).collect();
+    let field = if quoted {
+        format!("\"{field}\"")
+    } else {
+        field
+    };
+    let record = [field.as_str(); 4].join("🔥");
+    let mut result = String::new();
+    for _ in 0..rows {
+        result.push_str(&record);
+        result.push('\n');
+    }
+    result
+}
+
 fn generate_records(rows: usize, cols: usize) -> Vec<Vec<String>> {
     (0..rows)
         .map(|row| (0..cols).map(|col| format!("value_{row}_{col}")).collect())
@@ -171,6 +189,112 @@ fn bench_parse_strict_mode(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_parse_borrowed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_borrowed");
+    let parser = EsvParser::new();
+
+    for size in [10, 100, 1000].iter() {
+        let input = generate_simple_esv(*size, 5);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| parser.parse_borrowed(black_box(input)));
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_scan_long_fields(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_long_fields");
+
+    for field_len in [16, 256, 4096].iter() {
+        let input = generate_long_fields(100, *field_len, false, "abcdefghij");
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(field_len),
+            &input,
+            |b, input| {
+                b.iter(|| parse(black_box(input)));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_scan_long_quoted_fields(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_long_quoted_fields");
+
+    for field_len in [16, 256, 4096].iter() {
+        let input = generate_long_fields(100, *field_len, true, "abc🔥def,ghi");
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(field_len),
+            &input,
+            |b, input| {
+                b.iter(|| parse(black_box(input)));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_scan_non_ascii_fields(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_non_ascii_fields");
+
+    for field_len in [16, 256, 4096].iter() {
+        // Text sharing the separator's UTF-8 lead byte exercises the slow path
+        let input = generate_long_fields(100, *field_len, false, "日本語テキスト😀🎉");
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(field_len),
+            &input,
+            |b, input| {
+                b.iter(|| parse(black_box(input)));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_parse_parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_parallel");
+    let input = generate_esv_with_newlines(50_000, 5);
+    let parser = EsvParser::new();
+    group.throughput(Throughput::Bytes(input.len() as u64));
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| parser.parse(black_box(&input)));
+    });
+    for threads in [2, 4, 8].iter() {
+        group.bench_with_input(
+            BenchmarkId::new("threads", threads),
+            threads,
+            |b, &threads| {
+                b.iter(|| parser.parse_parallel(black_box(&input), threads));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_reader(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reader");
+
+    for size in [10, 100, 1000].iter() {
+        let input = generate_esv_with_newlines(*size, 5);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &input, |b, input| {
+            b.iter(|| EsvReader::new(black_box(input.as_bytes())).count());
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_serialize_simple(c: &mut Criterion) {
     let mut group = c.benchmark_group("serialize_simple");
 
@@ -300,6 +424,12 @@ criterion_group!(
     bench_parse_with_newlines,
     bench_parse_custom_separator,
     bench_parse_strict_mode,
+    bench_parse_borrowed,
+    bench_scan_long_fields,
+    bench_scan_long_quoted_fields,
+    bench_scan_non_ascii_fields,
+    bench_parse_parallel,
+    bench_reader,
     bench_serialize_simple,
     bench_serialize_with_headers,
     bench_serialize_always_quote,
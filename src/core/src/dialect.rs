@@ -0,0 +1,360 @@
+//! Shared dialect settings
+//!
+//! An `EsvDialect` bundles the separator, separator policy, header, strictness,
+//! quoting and line-ending settings so parsers, serializers and tools can be
+//! configured from one definition, typically loaded from a small config file.
+
+use std::fmt;
+
+use crate::error::EsvError;
+use crate::quoting::DEFAULT_QUOTE;
+use crate::EscapeStyle;
+use crate::EsvParser;
+use crate::EsvSerializer;
+use crate::LineEnding;
+use crate::SeparatorPolicy;
+use crate::DEFAULT_SEPARATOR;
+
+/// Dialect settings shared by `EsvParser` and `EsvSerializer`
+///
+/// Dialects round-trip through a line-based config format: one `key = value` setting
+/// per line, with blank lines and `#` comments ignored.
+///
+/// ```text
+/// separator = 🌟
+/// policy = allowlist 🌟 ⭐
+/// headers = true
+/// strict = true
+/// always_quote = false
+/// quote = '
+/// escape = backslash
+/// comment_prefix = #
+/// skip_blank_lines = true
+/// line_ending = crlf
+/// ```
+///
+/// Settings missing from a config keep their default value.
+// Each flag is an independent setting
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EsvDialect {
+    /// Field separator (`separator`)
+    pub separator: String,
+    /// Which separators are accepted (`policy = strict | permissive | allowlist ... |
+    /// denylist ...`)
+    pub separator_policy: SeparatorPolicy,
+    /// Whether the first record is a header row (`headers`)
+    pub has_headers: bool,
+    /// Whether every record must have the same number of fields (`strict`)
+    pub strict_field_count: bool,
+    /// Whether the serializer quotes every field (`always_quote`)
+    pub always_quote: bool,
+    /// Character that encloses quoted fields (`quote`)
+    pub quote: char,
+    /// How quotes are escaped inside quoted fields (`escape = doubled | backslash`)
+    pub escape_style: EscapeStyle,
+    /// Prefix of comment lines, if any (`comment_prefix`)
+    pub comment_prefix: Option<String>,
+    /// Whether parsers skip empty lines between records (`skip_blank_lines`)
+    pub skip_blank_lines: bool,
+    /// Line ending written by the serializer (`line_ending = lf | crlf`)
+    pub line_ending: LineEnding,
+}
+
+impl Default for EsvDialect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EsvDialect {
+    /// Create a dialect with the default settings of `EsvParser` and `EsvSerializer`
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            separator: DEFAULT_SEPARATOR.to_string(),
+            separator_policy: SeparatorPolicy::StrictEmoji,
+            has_headers: false,
+            strict_field_count: false,
+            always_quote: false,
+            quote: DEFAULT_QUOTE,
+            escape_style: EscapeStyle::Doubled,
+            comment_prefix: None,
+            skip_blank_lines: false,
+            line_ending: LineEnding::Lf,
+        }
+    }
+
+    /// Parse a dialect from its config file format
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::InvalidDialect` naming the offending line if a line is not
+    /// in `key = value` form, the key is unknown or the value is invalid.
+    pub fn parse(config: &str) -> Result<Self, EsvError> {
+        let mut dialect = EsvDialect::new();
+
+        for (index, line) in config.lines().enumerate() {
+            let line_num = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |reason: String| EsvError::InvalidDialect {
+                line: line_num,
+                reason,
+            };
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected key = value, found '{line}'")))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "separator" if !value.is_empty() => dialect.separator = value.to_string(),
+                "separator" => return Err(invalid("separator must not be empty".to_string())),
+                "policy" => {
+                    dialect.separator_policy = parse_policy(value)
+                        .ok_or_else(|| invalid(format!("invalid policy '{value}'")))?;
+                }
+                "comment_prefix" => {
+                    dialect.comment_prefix = Some(value.to_string()).filter(|p| !p.is_empty());
+                }
+                "headers" | "strict" | "always_quote" | "skip_blank_lines" => {
+                    let flag = match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => return Err(invalid(format!("invalid {key} value '{value}'"))),
+                    };
+                    match key {
+                        "headers" => dialect.has_headers = flag,
+                        "strict" => dialect.strict_field_count = flag,
+                        "always_quote" => dialect.always_quote = flag,
+                        _ => dialect.skip_blank_lines = flag,
+                    }
+                }
+                "quote" => {
+                    let mut chars = value.chars();
+                    dialect.quote = match (chars.next(), chars.next()) {
+                        (Some(quote), None) => quote,
+                        _ => return Err(invalid(format!("invalid quote value '{value}'"))),
+                    };
+                }
+                "escape" => {
+                    dialect.escape_style = match value {
+                        "doubled" => EscapeStyle::Doubled,
+                        "backslash" => EscapeStyle::Backslash,
+                        _ => return Err(invalid(format!("invalid escape value '{value}'"))),
+                    };
+                }
+                "line_ending" => {
+                    dialect.line_ending = match value {
+                        "lf" => LineEnding::Lf,
+                        "crlf" => LineEnding::Crlf,
+                        _ => return Err(invalid(format!("invalid line_ending value '{value}'"))),
+                    };
+                }
+                _ => return Err(invalid(format!("unknown setting '{key}'"))),
+            }
+        }
+
+        Ok(dialect)
+    }
+
+    /// Build a parser configured with this dialect
+    #[must_use]
+    pub fn parser(&self) -> EsvParser {
+        EsvParser::from_dialect(self)
+    }
+
+    /// Build a serializer configured with this dialect
+    #[must_use]
+    pub fn serializer(&self) -> EsvSerializer {
+        EsvSerializer::from_dialect(self)
+    }
+}
+
+/// Parse a `policy` value: `strict`, `permissive`, or `allowlist`/`denylist` followed
+/// by whitespace-separated separators
+fn parse_policy(value: &str) -> Option<SeparatorPolicy> {
+    let mut words = value.split_whitespace();
+    let policy = match words.next()? {
+        "strict" => SeparatorPolicy::StrictEmoji,
+        "permissive" => SeparatorPolicy::Permissive,
+        "allowlist" => return Some(SeparatorPolicy::allowlist(words)),
+        "denylist" => return Some(SeparatorPolicy::denylist(words)),
+        _ => return None,
+    };
+    words.next().is_none().then_some(policy)
+}
+
+impl fmt::Display for EsvDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "separator = {}", self.separator)?;
+        match &self.separator_policy {
+            SeparatorPolicy::StrictEmoji => writeln!(f, "policy = strict")?,
+            SeparatorPolicy::Permissive => writeln!(f, "policy = permissive")?,
+            SeparatorPolicy::Allowlist(separators) => {
+                writeln!(f, "policy = allowlist {}", separators.join(" "))?;
+            }
+            SeparatorPolicy::Denylist(separators) => {
+                writeln!(f, "policy = denylist {}", separators.join(" "))?;
+            }
+        }
+        writeln!(f, "headers = {}", self.has_headers)?;
+        writeln!(f, "strict = {}", self.strict_field_count)?;
+        writeln!(f, "always_quote = {}", self.always_quote)?;
+        writeln!(f, "quote = {}", self.quote)?;
+        let escape = match self.escape_style {
+            EscapeStyle::Doubled => "doubled",
+            EscapeStyle::Backslash => "backslash",
+        };
+        writeln!(f, "escape = {escape}")?;
+        if let Some(prefix) = &self.comment_prefix {
+            writeln!(f, "comment_prefix = {prefix}")?;
+        }
+        writeln!(f, "skip_blank_lines = {}", self.skip_blank_lines)?;
+        let line_ending = match self.line_ending {
+            LineEnding::Lf => "lf",
+            LineEnding::Crlf => "crlf",
+        };
+        writeln!(f, "line_ending = {line_ending}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EsvDocument;
+
+    #[test]
+    fn test_parse_dialect() {
+        let config = "\
+# Shared pipeline dialect
+separator = 🌟
+
+headers = true
+strict = true
+always_quote = true
+quote = '
+escape = backslash
+comment_prefix = //
+skip_blank_lines = true
+line_ending = crlf
+";
+        let dialect = EsvDialect::parse(config).unwrap();
+        assert_eq!(
+            dialect,
+            EsvDialect {
+                separator: "🌟".to_string(),
+                separator_policy: SeparatorPolicy::StrictEmoji,
+                has_headers: true,
+                strict_field_count: true,
+                always_quote: true,
+                quote: '\'',
+                escape_style: EscapeStyle::Backslash,
+                comment_prefix: Some("//".to_string()),
+                skip_blank_lines: true,
+                line_ending: LineEnding::Crlf,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_dialect_is_default() {
+        assert_eq!(EsvDialect::parse("").unwrap(), EsvDialect::new());
+        assert_eq!(EsvDialect::parse("# nothing\n").unwrap(), EsvDialect::new());
+    }
+
+    #[test]
+    fn test_parse_policies() {
+        let policy = |value: &str| {
+            EsvDialect::parse(&format!("policy = {value}"))
+                .unwrap()
+                .separator_policy
+        };
+        assert_eq!(policy("strict"), SeparatorPolicy::StrictEmoji);
+        assert_eq!(policy("permissive"), SeparatorPolicy::Permissive);
+        assert_eq!(
+            policy("allowlist ⭐ §"),
+            SeparatorPolicy::allowlist(["⭐", "§"])
+        );
+        assert_eq!(policy("denylist ➖"), SeparatorPolicy::denylist(["➖"]));
+    }
+
+    #[test]
+    fn test_parse_invalid_dialect() {
+        for (config, line) in [
+            ("separator 🌟", 1),
+            ("# comment\nheaders = yes", 2),
+            ("\n\nline_ending = cr", 3),
+            ("policy = strict ⭐", 1),
+            ("policy = lenient", 1),
+            ("separator =", 1),
+            ("seperator = 🌟", 1),
+            ("quote = ''", 1),
+            ("quote =", 1),
+            ("escape = none", 1),
+            ("skip_blank_lines = 1", 1),
+        ] {
+            assert!(
+                matches!(
+                    EsvDialect::parse(config),
+                    Err(EsvError::InvalidDialect { line: l, .. }) if l == line
+                ),
+                "config: {config:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let dialect = EsvDialect {
+            separator: "§".to_string(),
+            separator_policy: SeparatorPolicy::allowlist(["§", "⭐"]),
+            has_headers: true,
+            strict_field_count: false,
+            always_quote: true,
+            quote: '`',
+            escape_style: EscapeStyle::Backslash,
+            comment_prefix: Some("#".to_string()),
+            skip_blank_lines: true,
+            line_ending: LineEnding::Crlf,
+        };
+        assert_eq!(EsvDialect::parse(&dialect.to_string()).unwrap(), dialect);
+        assert_eq!(
+            EsvDialect::parse(&EsvDialect::new().to_string()).unwrap(),
+            EsvDialect::new()
+        );
+    }
+
+    #[test]
+    fn test_dialect_builds_parser_and_serializer() {
+        let dialect = EsvDialect {
+            separator: "§".to_string(),
+            separator_policy: SeparatorPolicy::Permissive,
+            has_headers: true,
+            always_quote: true,
+            ..EsvDialect::new()
+        };
+        let doc =
+            EsvDocument::with_headers(vec!["name".to_string()], vec![vec!["Alice".to_string()]]);
+
+        let output = dialect.serializer().try_serialize(&doc).unwrap();
+        assert_eq!(output, "\"name\"\n\"Alice\"\n");
+        assert_eq!(dialect.parser().parse(&output).unwrap(), doc);
+
+        let dialect = EsvDialect::parse("quote = '\nescape = backslash").unwrap();
+        let doc = EsvDocument::new(vec![vec!["it's\nhere".to_string()]]);
+        let output = dialect.serializer().serialize(&doc);
+        assert_eq!(output, "'it\\'s\\nhere'\n");
+        assert_eq!(dialect.parser().parse(&output).unwrap(), doc);
+
+        let dialect = EsvDialect::parse("comment_prefix = #\nskip_blank_lines = true").unwrap();
+        let doc = EsvDocument::new(vec![vec!["#1".to_string()], vec!["x".to_string()]]);
+        let output = dialect.serializer().serialize(&doc);
+        assert_eq!(output, "\"#1\"\nx\n");
+        let input = format!("# note\n\n{output}");
+        assert_eq!(dialect.parser().parse(&input).unwrap(), doc);
+    }
+}
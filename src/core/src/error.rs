@@ -1,42 +1,135 @@
 //! Error types for ESV parsing and serialization
 
 use std::fmt;
+use std::fmt::Write;
+use std::io;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The part of the input an error points at
+///
+/// Offsets are byte offsets into the parsed input (including any pragma line), so
+/// `&input[span.start..span.end]` is the offending text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// Byte offset of the first offending byte
+    pub start: usize,
+    /// Byte offset just past the offending text
+    pub end: usize,
+    /// Column of `start` counted in grapheme clusters (1-based), as shown by editors
+    pub grapheme_column: usize,
+}
 
 /// Errors that can occur during ESV parsing or serialization
 #[derive(Debug, Clone, PartialEq)]
 pub enum EsvError {
-    /// Unclosed quoted field
-    UnclosedQuote { line: usize, column: usize },
+    /// Unclosed quoted field, spanning from the opening quote to the end of the input
+    UnclosedQuote {
+        line: usize,
+        column: usize,
+        span: Span,
+    },
+    /// Double quote inside an unquoted field (only with strict syntax)
+    BareQuote {
+        line: usize,
+        column: usize,
+        span: Span,
+    },
     /// Unexpected character after closing quote
     UnexpectedCharAfterQuote {
         line: usize,
         column: usize,
         found: char,
+        span: Span,
     },
-    /// Inconsistent field count across records
+    /// Inconsistent field count across records, spanning the offending record from
+    /// its first line to its last
     InconsistentFieldCount {
         expected: usize,
         found: usize,
         line: usize,
+        end_line: usize,
+        span: Span,
+    },
+    /// Record whose field count differs from the header row (strict field count with
+    /// headers), naming the headers it has no field for or counting the fields past
+    /// the last header
+    HeaderMismatch {
+        missing: Vec<String>,
+        extra: usize,
+        line: usize,
+        end_line: usize,
+        span: Span,
+    },
+    /// Empty or whitespace-only header name (with `HeaderPolicy::Reject`); `field`
+    /// is the 1-based field number
+    EmptyHeader {
+        field: usize,
+        line: usize,
+        span: Span,
+    },
+    /// Header name with leading or trailing whitespace (with `HeaderPolicy::Reject`)
+    HeaderWhitespace {
+        name: String,
+        field: usize,
+        line: usize,
+        span: Span,
+    },
+    /// Header name repeating an earlier one (with `HeaderPolicy::Reject`)
+    DuplicateHeader {
+        name: String,
+        field: usize,
+        first_field: usize,
+        line: usize,
+        span: Span,
     },
     /// Empty input
     EmptyInput,
-    /// Invalid UTF-8 in input
-    InvalidUtf8,
-    /// Invalid separator - must be an emoji
-    InvalidSeparator { separator: char },
+    /// Invalid UTF-8 in input, at the given position of the first invalid byte
+    InvalidUtf8 {
+        line: usize,
+        column: usize,
+        offset: usize,
+        span: Span,
+    },
+    /// Invalid separator - rejected by the separator policy
+    InvalidSeparator {
+        separator: String,
+        reason: SeparatorRejection,
+    },
+    /// Invalid quote character for the separator and escape style
+    InvalidQuote { quote: char, reason: String },
+    /// Malformed `#!esv` pragma line
+    InvalidPragma { reason: String },
+    /// A pragma line is required but the input does not start with one
+    MissingPragma,
+    /// Malformed dialect config
+    InvalidDialect { line: usize, reason: String },
+    /// I/O failure while reading or writing a stream
+    Io {
+        kind: io::ErrorKind,
+        message: String,
+    },
 }
 
 impl fmt::Display for EsvError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            EsvError::UnclosedQuote { line, column } => {
+            EsvError::UnclosedQuote { line, column, .. } => {
                 write!(f, "unclosed quote at line {line}, column {column}")
             }
+            EsvError::BareQuote { line, column, .. } => {
+                write!(
+                    f,
+                    "bare quote in unquoted field at line {line}, column {column}"
+                )
+            }
             EsvError::UnexpectedCharAfterQuote {
                 line,
                 column,
                 found,
+                ..
             } => {
                 write!(
                     f,
@@ -47,26 +140,338 @@ impl fmt::Display for EsvError {
                 expected,
                 found,
                 line,
+                end_line,
+                ..
             } => {
+                write!(f, "inconsistent field count at ")?;
+                write_lines(f, *line, *end_line)?;
+                write!(f, ": expected {expected} fields, found {found}")
+            }
+            EsvError::HeaderMismatch {
+                missing,
+                extra,
+                line,
+                end_line,
+                ..
+            } => {
+                write!(f, "record at ")?;
+                write_lines(f, *line, *end_line)?;
+                match missing.as_slice() {
+                    [] if *extra == 1 => write!(f, " has 1 field past the last header"),
+                    [] => write!(f, " has {extra} fields past the last header"),
+                    [header] => write!(f, " has no field for header '{header}'"),
+                    headers => write!(f, " has no fields for headers '{}'", headers.join("', '")),
+                }
+            }
+            EsvError::EmptyHeader { field, line, .. } => {
+                write!(f, "empty header name in field {field} at line {line}")
+            }
+            EsvError::HeaderWhitespace {
+                name, field, line, ..
+            } => write!(
+                f,
+                "header '{name}' in field {field} at line {line} has leading or trailing whitespace"
+            ),
+            EsvError::DuplicateHeader {
+                name,
+                field,
+                first_field,
+                line,
+                ..
+            } => write!(
+                f,
+                "duplicate header '{name}' in field {field} at line {line} (first in field {first_field})"
+            ),
+            EsvError::EmptyInput => write!(f, "empty input"),
+            EsvError::InvalidUtf8 {
+                line,
+                column,
+                offset,
+                ..
+            } => write!(
+                f,
+                "invalid UTF-8 at line {line}, column {column} (byte offset {offset})"
+            ),
+            EsvError::InvalidSeparator { separator, reason } => {
+                let code_points = separator
+                    .chars()
+                    .map(|c| format!("U+{:04X}", c as u32))
+                    .collect::<Vec<_>>()
+                    .join(" ");
                 write!(
                     f,
-                    "inconsistent field count at line {line}: expected {expected} fields, found {found}"
+                    "invalid separator '{separator}' ({code_points}): {reason}"
                 )
             }
-            EsvError::EmptyInput => write!(f, "empty input"),
-            EsvError::InvalidUtf8 => write!(f, "invalid UTF-8 in input"),
-            EsvError::InvalidSeparator { separator } => {
+            EsvError::InvalidQuote { quote, reason } => {
+                write!(f, "invalid quote character '{quote}': {reason}")
+            }
+            EsvError::InvalidPragma { reason } => write!(f, "invalid pragma: {reason}"),
+            EsvError::MissingPragma => write!(f, "missing required '#!esv' pragma line"),
+            EsvError::InvalidDialect { line, reason } => {
+                write!(f, "invalid dialect at line {line}: {reason}")
+            }
+            EsvError::Io { message, .. } => write!(f, "I/O error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for EsvError {}
+
+impl EsvError {
+    /// The part of the input the error points at, for errors with a position
+    #[must_use]
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            EsvError::UnclosedQuote { span, .. }
+            | EsvError::BareQuote { span, .. }
+            | EsvError::UnexpectedCharAfterQuote { span, .. }
+            | EsvError::InconsistentFieldCount { span, .. }
+            | EsvError::HeaderMismatch { span, .. }
+            | EsvError::EmptyHeader { span, .. }
+            | EsvError::HeaderWhitespace { span, .. }
+            | EsvError::DuplicateHeader { span, .. }
+            | EsvError::InvalidUtf8 { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+
+    /// Render the error message followed by the offending line of `source`, with the
+    /// error's span underlined by carets
+    ///
+    /// `source` must be the input the error was reported for. Only the first line of
+    /// a multi-line span is shown, and errors without a span render as their message
+    /// alone.
+    ///
+    /// ```text
+    /// unclosed quote at line 2, column 3
+    ///  --> line 2, grapheme column 3, bytes 16..21
+    ///   |
+    /// 2 | a🔥"open
+    ///   |    ^^^^^
+    /// ```
+    #[must_use]
+    pub fn render(&self, source: &str) -> String {
+        render_snippet(self.to_string(), self.span(), source)
+    }
+
+    /// Turn an `InconsistentFieldCount` error into `HeaderMismatch`, naming the headers
+    /// of the header row the record was compared against
+    pub(crate) fn name_headers<S: AsRef<str>>(self, headers: &[S]) -> Self {
+        match self {
+            EsvError::InconsistentFieldCount {
+                found,
+                line,
+                end_line,
+                span,
+                ..
+            } => EsvError::HeaderMismatch {
+                missing: headers
+                    .iter()
+                    .skip(found)
+                    .map(|header| header.as_ref().to_string())
+                    .collect(),
+                extra: found.saturating_sub(headers.len()),
+                line,
+                end_line,
+                span,
+            },
+            error => error,
+        }
+    }
+
+    /// Shift the line number of a positioned error by `lines`
+    pub(crate) fn shift_lines(mut self, lines: usize) -> Self {
+        match &mut self {
+            EsvError::UnclosedQuote { line, .. }
+            | EsvError::BareQuote { line, .. }
+            | EsvError::UnexpectedCharAfterQuote { line, .. }
+            | EsvError::EmptyHeader { line, .. }
+            | EsvError::HeaderWhitespace { line, .. }
+            | EsvError::DuplicateHeader { line, .. }
+            | EsvError::InvalidUtf8 { line, .. } => *line += lines,
+            EsvError::InconsistentFieldCount { line, end_line, .. }
+            | EsvError::HeaderMismatch { line, end_line, .. } => {
+                *line += lines;
+                *end_line += lines;
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Shift the byte offsets of a positioned error by `bytes`
+    pub(crate) fn shift_offsets(mut self, bytes: usize) -> Self {
+        match &mut self {
+            EsvError::UnclosedQuote { span, .. }
+            | EsvError::BareQuote { span, .. }
+            | EsvError::UnexpectedCharAfterQuote { span, .. }
+            | EsvError::InconsistentFieldCount { span, .. }
+            | EsvError::HeaderMismatch { span, .. }
+            | EsvError::EmptyHeader { span, .. }
+            | EsvError::HeaderWhitespace { span, .. }
+            | EsvError::DuplicateHeader { span, .. } => {
+                span.start += bytes;
+                span.end += bytes;
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Build an `InvalidUtf8` error for `len` invalid bytes at `offset`, preceded by
+    /// the valid text `preceding` that starts at the beginning of line `start_line`
+    pub(crate) fn invalid_utf8(
+        preceding: &str,
+        start_line: usize,
+        offset: usize,
+        len: usize,
+    ) -> Self {
+        let (column, grapheme_column) =
+            columns(&preceding[line_start(preceding, preceding.len())..]);
+        EsvError::InvalidUtf8 {
+            line: start_line + count_line_breaks(preceding),
+            column,
+            offset,
+            span: Span {
+                start: offset,
+                end: offset + len,
+                grapheme_column,
+            },
+        }
+    }
+}
+
+/// Write `line N`, or `lines N-M` for a record spanning several lines
+fn write_lines(f: &mut fmt::Formatter<'_>, line: usize, end_line: usize) -> fmt::Result {
+    if end_line > line {
+        write!(f, "lines {line}-{end_line}")
+    } else {
+        write!(f, "line {line}")
+    }
+}
+
+/// Append the line of `source` containing `span`, with the span underlined, to
+/// `output`
+pub(crate) fn render_snippet(mut output: String, span: Option<Span>, source: &str) -> String {
+    let Some(span) = span else {
+        return output;
+    };
+    if span.start > source.len() || !source.is_char_boundary(span.start) {
+        return output;
+    }
+
+    let line_start = line_start(source, span.start);
+    let line_end = source[span.start..]
+        .find(['\r', '\n'])
+        .map_or(source.len(), |index| span.start + index);
+    let mut end = span.end.clamp(span.start, line_end);
+    while !source.is_char_boundary(end) {
+        end += 1;
+    }
+
+    // Tabs are copied into the indent so the carets line up however they render
+    let indent: String = source[line_start..span.start]
+        .graphemes(true)
+        .map(|g| {
+            if g == "\t" {
+                "\t".to_string()
+            } else {
+                " ".repeat(g.width())
+            }
+        })
+        .collect();
+    let carets = "^".repeat(source[span.start..end].width().max(1));
+
+    let line = count_line_breaks(&source[..line_start]) + 1;
+    let gutter = " ".repeat(line.to_string().len());
+    let _ = write!(
+        output,
+        "\n{gutter}--> line {line}, grapheme column {}, bytes {}..{}\n\
+         {gutter} |\n\
+         {line} | {}\n\
+         {gutter} | {indent}{carets}",
+        span.grapheme_column,
+        span.start,
+        span.end,
+        &source[line_start..line_end],
+    );
+    output
+}
+
+/// Byte offset where the line containing byte offset `pos` starts
+pub(crate) fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind(['\r', '\n']).map_or(0, |index| index + 1)
+}
+
+/// Character and grapheme columns (1-based) just past `line_prefix`, the text
+/// between the start of a line and a position on it
+pub(crate) fn columns(line_prefix: &str) -> (usize, usize) {
+    (
+        line_prefix.chars().count() + 1,
+        line_prefix.graphemes(true).count() + 1,
+    )
+}
+
+/// Number of CR, LF or CRLF line breaks in `text`
+fn count_line_breaks(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    bytes
+        .iter()
+        .enumerate()
+        .filter(|&(index, &byte)| {
+            byte == b'\n' || (byte == b'\r' && bytes.get(index + 1) != Some(&b'\n'))
+        })
+        .count()
+}
+
+/// Why a `SeparatorPolicy` rejected a separator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeparatorRejection {
+    /// Empty, or contains a double quote or line break (rejected by every policy)
+    Reserved,
+    /// Not an emoji or emoji sequence (strict emoji and denylist policies)
+    NotEmoji,
+    /// Not in the allowlist (allowlist policy)
+    NotAllowlisted,
+    /// Listed in the denylist (denylist policy)
+    Denylisted,
+    /// Not a single non-ASCII grapheme cluster (permissive policy)
+    NotPermitted,
+}
+
+impl fmt::Display for SeparatorRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeparatorRejection::Reserved => {
                 write!(
                     f,
-                    "invalid separator '{}' (U+{:04X}): separator must be an emoji",
-                    separator, *separator as u32
+                    "separator must not be empty or contain quotes or line breaks"
                 )
             }
+            SeparatorRejection::NotEmoji => write!(f, "separator must be an emoji"),
+            SeparatorRejection::NotAllowlisted => {
+                write!(f, "separator is not in the allowlist policy")
+            }
+            SeparatorRejection::Denylisted => {
+                write!(f, "separator is forbidden by the denylist policy")
+            }
+            SeparatorRejection::NotPermitted => write!(
+                f,
+                "separator must be a single non-ASCII grapheme cluster under the permissive policy"
+            ),
         }
     }
 }
 
-impl std::error::Error for EsvError {}
+impl From<io::Error> for EsvError {
+    fn from(err: io::Error) -> Self {
+        EsvError::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -74,13 +479,18 @@ mod tests {
 
     #[test]
     fn test_error_display() {
-        let err = EsvError::UnclosedQuote { line: 1, column: 5 };
+        let err = EsvError::UnclosedQuote {
+            line: 1,
+            column: 5,
+            span: Span::default(),
+        };
         assert_eq!(err.to_string(), "unclosed quote at line 1, column 5");
 
         let err = EsvError::UnexpectedCharAfterQuote {
             line: 2,
             column: 10,
             found: 'x',
+            span: Span::default(),
         };
         assert_eq!(
             err.to_string(),
@@ -91,6 +501,8 @@ mod tests {
             expected: 3,
             found: 2,
             line: 5,
+            end_line: 5,
+            span: Span::default(),
         };
         assert_eq!(
             err.to_string(),
@@ -100,13 +512,192 @@ mod tests {
         let err = EsvError::EmptyInput;
         assert_eq!(err.to_string(), "empty input");
 
-        let err = EsvError::InvalidUtf8;
-        assert_eq!(err.to_string(), "invalid UTF-8 in input");
+        let err = EsvError::InvalidUtf8 {
+            line: 2,
+            column: 4,
+            offset: 9,
+            span: Span::default(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid UTF-8 at line 2, column 4 (byte offset 9)"
+        );
 
-        let err = EsvError::InvalidSeparator { separator: ',' };
+        let err = EsvError::InvalidSeparator {
+            separator: ",".to_string(),
+            reason: SeparatorRejection::NotEmoji,
+        };
         assert_eq!(
             err.to_string(),
             "invalid separator ',' (U+002C): separator must be an emoji"
         );
+
+        let err = EsvError::InvalidSeparator {
+            separator: "a🔥".to_string(),
+            reason: SeparatorRejection::NotEmoji,
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid separator 'a🔥' (U+0061 U+1F525): separator must be an emoji"
+        );
+
+        let err = EsvError::InvalidSeparator {
+            separator: "🔥".to_string(),
+            reason: SeparatorRejection::NotAllowlisted,
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid separator '🔥' (U+1F525): separator is not in the allowlist policy"
+        );
+
+        let err = EsvError::InvalidSeparator {
+            separator: "➖".to_string(),
+            reason: SeparatorRejection::Denylisted,
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid separator '➖' (U+2796): separator is forbidden by the denylist policy"
+        );
+
+        let err = EsvError::InvalidPragma {
+            reason: "invalid header value 'yes'".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid pragma: invalid header value 'yes'"
+        );
+
+        let err = EsvError::MissingPragma;
+        assert_eq!(err.to_string(), "missing required '#!esv' pragma line");
+
+        let err = EsvError::InvalidDialect {
+            line: 3,
+            reason: "unknown setting 'seperator'".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid dialect at line 3: unknown setting 'seperator'"
+        );
+
+        let err = EsvError::from(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "stream closed",
+        ));
+        assert_eq!(err.to_string(), "I/O error: stream closed");
+    }
+
+    #[test]
+    fn test_invalid_utf8_position() {
+        assert_eq!(
+            EsvError::invalid_utf8("", 1, 0, 1),
+            EsvError::InvalidUtf8 {
+                line: 1,
+                column: 1,
+                offset: 0,
+                span: Span {
+                    start: 0,
+                    end: 1,
+                    grapheme_column: 1,
+                },
+            }
+        );
+        assert_eq!(
+            EsvError::invalid_utf8("a🔥b\r\nc\rde\u{301}", 3, 20, 2),
+            EsvError::InvalidUtf8 {
+                line: 5,
+                column: 4,
+                offset: 20,
+                span: Span {
+                    start: 20,
+                    end: 22,
+                    grapheme_column: 3,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_span() {
+        let span = Span {
+            start: 3,
+            end: 4,
+            grapheme_column: 2,
+        };
+        let err = EsvError::UnexpectedCharAfterQuote {
+            line: 1,
+            column: 2,
+            found: 'x',
+            span,
+        };
+        assert_eq!(err.span(), Some(span));
+        assert_eq!(EsvError::EmptyInput.span(), None);
+        assert_eq!(
+            err.shift_offsets(10).span(),
+            Some(Span {
+                start: 13,
+                end: 14,
+                grapheme_column: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let source = "ok\r\n日本🔥\"a\"x🔥b\n";
+        let err = EsvError::UnexpectedCharAfterQuote {
+            line: 2,
+            column: 7,
+            found: 'x',
+            span: Span {
+                start: 17,
+                end: 18,
+                grapheme_column: 7,
+            },
+        };
+        assert_eq!(
+            err.render(source),
+            "unexpected character 'x' after closing quote at line 2, column 7
+ --> line 2, grapheme column 7, bytes 17..18
+  |
+2 | 日本🔥\"a\"x🔥b
+  |          ^"
+        );
+
+        // Multi-line spans underline the rest of their first line; tabs are kept
+        let source = "a\t\"b👍🏽\nc";
+        let err = EsvError::UnclosedQuote {
+            line: 1,
+            column: 3,
+            span: Span {
+                start: 2,
+                end: source.len(),
+                grapheme_column: 3,
+            },
+        };
+        assert_eq!(
+            err.render(source),
+            "unclosed quote at line 1, column 3
+ --> line 1, grapheme column 3, bytes 2..14
+  |
+1 | a\t\"b👍🏽
+  |  \t^^^^"
+        );
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        assert_eq!(EsvError::EmptyInput.render("a"), "empty input");
+
+        // A span that does not fit the source falls back to the message
+        let err = EsvError::UnclosedQuote {
+            line: 1,
+            column: 1,
+            span: Span {
+                start: 50,
+                end: 60,
+                grapheme_column: 1,
+            },
+        };
+        assert_eq!(err.render("short"), err.to_string());
     }
 }
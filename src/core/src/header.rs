@@ -0,0 +1,167 @@
+//! Header row checks
+//!
+//! Duplicate, empty or whitespace-padded header names make columns ambiguous once
+//! records are keyed by header (as in JSON object conversions). A `HeaderPolicy`
+//! decides whether such names are kept, rejected or renamed.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::error::EsvError;
+use crate::error::Span;
+use crate::EsvPosition;
+
+/// How the parser treats duplicate, empty and whitespace-padded header names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderPolicy {
+    /// Keep header names as they are (the default)
+    #[default]
+    Accept,
+    /// Fail with `EsvError::EmptyHeader`, `EsvError::HeaderWhitespace` or
+    /// `EsvError::DuplicateHeader`
+    Reject,
+    /// Trim whitespace, name empty headers `column_N` after their field number and
+    /// suffix repeated names with their occurrence (`name`, `name_2`, `name_3`)
+    Rename,
+}
+
+impl HeaderPolicy {
+    /// Check or fix the header row parsed at `position`, returning every problem found
+    /// when rejecting
+    ///
+    /// `spans` holds the span of each header field; errors for fields without one
+    /// point at the whole row.
+    pub(crate) fn apply<S>(
+        self,
+        headers: &mut [S],
+        position: &EsvPosition,
+        spans: &[Span],
+    ) -> Vec<EsvError>
+    where
+        S: AsRef<str> + From<String>,
+    {
+        match self {
+            HeaderPolicy::Accept => Vec::new(),
+            HeaderPolicy::Reject => reject(headers, position, spans),
+            HeaderPolicy::Rename => {
+                rename(headers);
+                Vec::new()
+            }
+        }
+    }
+}
+
+fn reject<S: AsRef<str>>(headers: &[S], position: &EsvPosition, spans: &[Span]) -> Vec<EsvError> {
+    let mut errors = Vec::new();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+
+    for (index, name) in headers.iter().enumerate() {
+        let name = name.as_ref();
+        let field = index + 1;
+        let line = position.line;
+        let span = spans.get(index).copied().unwrap_or_else(|| position.span());
+        if name.trim().is_empty() {
+            errors.push(EsvError::EmptyHeader { field, line, span });
+        } else if name.trim() != name {
+            errors.push(EsvError::HeaderWhitespace {
+                name: name.to_string(),
+                field,
+                line,
+                span,
+            });
+        } else if let Some(&first_field) = seen.get(name) {
+            errors.push(EsvError::DuplicateHeader {
+                name: name.to_string(),
+                field,
+                first_field,
+                line,
+                span,
+            });
+        } else {
+            seen.insert(name, field);
+        }
+    }
+
+    errors
+}
+
+fn rename<S>(headers: &mut [S])
+where
+    S: AsRef<str> + From<String>,
+{
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for (index, header) in headers.iter_mut().enumerate() {
+        let name = match header.as_ref().trim() {
+            "" => format!("column_{}", index + 1),
+            name => name.to_string(),
+        };
+        let mut unique = name.clone();
+        let mut occurrence = 1;
+        while seen.contains(&unique) {
+            occurrence += 1;
+            unique = format!("{name}_{occurrence}");
+        }
+        if unique != header.as_ref() {
+            *header = S::from(unique.clone());
+        }
+        seen.insert(unique);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position() -> EsvPosition {
+        EsvPosition {
+            index: 0,
+            line: 1,
+            end_line: 1,
+            offset: 0,
+            len: 0,
+        }
+    }
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_accept_keeps_headers() {
+        let mut names = headers(&["a", "a", ""]);
+        assert!(HeaderPolicy::Accept
+            .apply(&mut names, &position(), &[])
+            .is_empty());
+        assert_eq!(names, headers(&["a", "a", ""]));
+    }
+
+    #[test]
+    fn test_reject_reports_every_problem() {
+        let mut names = headers(&["id", "", " name", "id", "  ", "id"]);
+        let errors = HeaderPolicy::Reject.apply(&mut names, &position(), &[]);
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "empty header name in field 2 at line 1",
+                "header ' name' in field 3 at line 1 has leading or trailing whitespace",
+                "duplicate header 'id' in field 4 at line 1 (first in field 1)",
+                "empty header name in field 5 at line 1",
+                "duplicate header 'id' in field 6 at line 1 (first in field 1)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut names = headers(&["id", "", " name", "id", "name", "id_2", "id"]);
+        assert!(HeaderPolicy::Rename
+            .apply(&mut names, &position(), &[])
+            .is_empty());
+        assert_eq!(
+            names,
+            headers(&["id", "column_2", "name", "id_2", "name_2", "id_2_2", "id_3"])
+        );
+    }
+}
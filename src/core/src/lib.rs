@@ -14,102 +14,245 @@
 //! 5. Each field may or may not be enclosed in double quotes.
 //! 6. Fields containing line breaks, double quotes, or the emoji separator should be
 //!    enclosed in double quotes.
+//!    The separator only splits fields where it forms a standalone grapheme cluster, so
+//!    emoji sequences such as ❤️‍🔥 in unquoted data are never cut apart.
 //! 7. Double quotes inside a field must be escaped by preceding with another double quote.
 //!
+//! # Dialect Pragma
+//!
+//! A file may start with a pragma line such as `#!esv sep=🌟 header=true eol=lf`
+//! declaring its separator, header presence and line ending. Parsers apply it by
+//! default (see [`PragmaMode`]) and serializers can emit it.
+//!
+//! # Dialects
+//!
+//! An [`EsvDialect`] collects the settings shared by parsers and serializers and can
+//! be loaded from a small `key = value` config file, so tools agree on one definition.
+//!
 //! # Default Separator
 //!
 //! The default emoji separator is 🔥 (fire emoji, U+1F525).
 //!
 //! # Separator Validation
 //!
-//! The separator must be an emoji character. ASCII characters and other non-emoji
-//! Unicode characters are not allowed. This ensures the format remains distinct from
-//! CSV and other traditional delimited formats.
+//! By default only emoji are accepted; a [`SeparatorPolicy`] on the parser or serializer
+//! can switch to an allowlist, a denylist or a permissive any-non-ASCII mode.
+//!
+//! Emoji classification is backed by tables generated from the Unicode `Emoji`,
+//! `Emoji_Presentation` and `Emoji_Modifier_Base` properties, pinned to the Unicode
+//! version in [`UNICODE_VERSION`].
+//!
+//! The separator must be an emoji: either a single emoji character or a complete emoji
+//! sequence forming one grapheme cluster (ZWJ sequences like 🏳️‍🌈, flags like 🇺🇸,
+//! skin-tone variants like 👍🏽, keycaps and tag sequences). ASCII characters and other
+//! non-emoji Unicode characters are not allowed. This ensures the format remains
+//! distinct from CSV and other traditional delimited formats.
+
+use std::borrow::Cow;
+
+use unicode_segmentation::GraphemeCursor;
+use unicode_segmentation::UnicodeSegmentation;
 
+mod dialect;
+mod emoji_tables;
 mod error;
+mod header;
+mod parallel;
 mod parser;
+mod policy;
+mod pragma;
+mod quoting;
+mod ragged;
+mod reader;
 mod serializer;
+mod sniffer;
+mod warning;
+mod writer;
 
+pub use dialect::EsvDialect;
+pub use emoji_tables::UNICODE_VERSION;
 pub use error::EsvError;
+pub use error::SeparatorRejection;
+pub use error::Span;
+pub use header::HeaderPolicy;
 pub use parser::EsvParser;
+pub use parser::ParseReport;
+pub use policy::SeparatorPolicy;
+pub use pragma::EsvPragma;
+pub use pragma::PragmaMode;
+pub use pragma::PRAGMA_PREFIX;
+pub use quoting::EscapeStyle;
+pub use ragged::RaggedPolicy;
+pub use reader::EsvReader;
 pub use serializer::EsvSerializer;
 pub use serializer::LineEnding;
+pub use sniffer::EsvSniffer;
+pub use sniffer::SniffResult;
+pub use warning::EsvWarning;
+pub use writer::EsvWriter;
 
 /// Default emoji separator (fire emoji 🔥)
 pub const DEFAULT_SEPARATOR: char = '🔥';
 
 /// Check if a character is an emoji
 ///
-/// This function checks if a character falls within common emoji Unicode ranges.
-/// It covers:
-/// - Miscellaneous Symbols and Pictographs (U+1F300-U+1F5FF)
-/// - Emoticons (U+1F600-U+1F64F)
-/// - Transport and Map Symbols (U+1F680-U+1F6FF)
-/// - Supplemental Symbols and Pictographs (U+1F900-U+1F9FF)
-/// - Symbols and Pictographs Extended-A (U+1FA00-U+1FA6F)
-/// - Symbols and Pictographs Extended-B (U+1FA70-U+1FAFF)
-/// - Dingbats (U+2700-U+27BF)
-/// - Miscellaneous Symbols (U+2600-U+26FF)
-/// - Miscellaneous Symbols and Arrows (U+2B00-U+2BFF)
-/// - Various other emoji ranges
+/// Backed by the Unicode `Emoji` property (see `UNICODE_VERSION` for the data
+/// version). ASCII digits, `#` and `*` carry the property too, but only form emoji as
+/// part of keycap sequences, so they are not considered emoji on their own.
 #[must_use]
 pub fn is_emoji(c: char) -> bool {
-    let code = c as u32;
-
-    // Common emoji ranges
-    matches!(
-        code,
-        // Miscellaneous Symbols and Pictographs
-        0x1F300..=0x1F5FF |
-        // Emoticons
-        0x1F600..=0x1F64F |
-        // Transport and Map Symbols
-        0x1F680..=0x1F6FF |
-        // Supplemental Symbols and Pictographs
-        0x1F900..=0x1F9FF |
-        // Symbols and Pictographs Extended-A
-        0x1FA00..=0x1FA6F |
-        // Symbols and Pictographs Extended-B
-        0x1FA70..=0x1FAFF |
-        // Dingbats (includes ❤ at U+2764)
-        0x2700..=0x27BF |
-        // Miscellaneous Symbols (includes ☀, ☁, etc.)
-        0x2600..=0x26FF |
-        // Miscellaneous Symbols and Arrows (includes ⭐ at U+2B50)
-        0x2B00..=0x2BFF |
-        // Enclosed Alphanumeric Supplement (some emoji)
-        0x1F100..=0x1F1FF |
-        // Mahjong Tiles
-        0x1F000..=0x1F02F |
-        // Domino Tiles
-        0x1F030..=0x1F09F |
-        // Playing Cards
-        0x1F0A0..=0x1F0FF |
-        // Miscellaneous Technical (some emoji like ⌚)
-        0x2300..=0x23FF |
-        // Arrows (some are emoji)
-        0x2190..=0x21FF |
-        // CJK Symbols (some emoji)
-        0x3000..=0x303F |
-        // Enclosed CJK Letters and Months
-        0x3200..=0x32FF |
-        // Geometric Shapes (some emoji)
-        0x25A0..=0x25FF |
-        // Box Drawing and Block Elements (some used as emoji)
-        0x2580..=0x259F
-    )
+    !c.is_ascii() && emoji_tables::contains(emoji_tables::EMOJI, c)
 }
 
-/// Validate that a separator is an emoji
+/// Check if a character is displayed as emoji by default
+///
+/// Backed by the Unicode `Emoji_Presentation` property. Emoji without it (such as
+/// ❤ or ✂) render as text unless followed by U+FE0F.
+#[must_use]
+pub fn is_emoji_presentation(c: char) -> bool {
+    emoji_tables::contains(emoji_tables::EMOJI_PRESENTATION, c)
+}
+
+/// Zero width joiner used to build emoji ZWJ sequences
+const ZWJ: char = '\u{200D}';
+
+/// Variation selector requesting emoji presentation
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+
+/// Combining mark turning a digit, `#` or `*` into a keycap emoji
+const COMBINING_KEYCAP: char = '\u{20E3}';
+
+/// Terminates an emoji tag sequence (e.g. 🏴󠁧󠁢󠁥󠁮󠁧󠁿)
+const CANCEL_TAG: char = '\u{E007F}';
+
+/// Check if a string is a single emoji or a complete emoji sequence
+///
+/// The string must form exactly one grapheme cluster. Accepted forms are:
+/// - A single emoji, optionally followed by U+FE0F (e.g. ❤️)
+/// - Emoji modifier sequences with a skin tone (e.g. 👍🏽)
+/// - Flag sequences made of two regional indicators (e.g. 🇺🇸)
+/// - Keycap sequences (e.g. 1️⃣)
+/// - Tag sequences (e.g. 🏴󠁧󠁢󠁥󠁮󠁧󠁿)
+/// - ZWJ sequences joining any of the above (e.g. 🏳️‍🌈, 👩🏽‍💻)
+#[must_use]
+pub fn is_emoji_sequence(s: &str) -> bool {
+    if s.graphemes(true).count() != 1 {
+        return false;
+    }
+
+    s.split(ZWJ).all(is_emoji_element)
+}
+
+/// Check one ZWJ-separated element of an emoji sequence
+fn is_emoji_element(element: &str) -> bool {
+    let chars: Vec<char> = element.chars().collect();
+
+    match chars.as_slice() {
+        [a, b] if is_regional_indicator(*a) && is_regional_indicator(*b) => true,
+        [base, rest @ ..] if is_keycap_base(*base) => {
+            matches!(
+                rest,
+                [COMBINING_KEYCAP] | [EMOJI_PRESENTATION_SELECTOR, COMBINING_KEYCAP]
+            )
+        }
+        [base, rest @ ..] if is_emoji(*base) && !is_regional_indicator(*base) => {
+            let rest = rest
+                .strip_prefix(&[EMOJI_PRESENTATION_SELECTOR])
+                .unwrap_or(rest);
+            let rest = match rest {
+                [modifier, rest @ ..] if is_emoji_modifier(*modifier) => {
+                    if !emoji_tables::contains(emoji_tables::EMOJI_MODIFIER_BASE, *base) {
+                        return false;
+                    }
+                    rest
+                }
+                _ => rest,
+            };
+            match rest {
+                [] => true,
+                [tags @ .., CANCEL_TAG] => !tags.is_empty() && tags.iter().all(|&c| is_tag(c)),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1F1E6}'..='\u{1F1FF}')
+}
+
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(c, '\u{1F3FB}'..='\u{1F3FF}')
+}
+
+fn is_keycap_base(c: char) -> bool {
+    c.is_ascii_digit() || c == '#' || c == '*'
+}
+
+fn is_tag(c: char) -> bool {
+    matches!(c, '\u{E0020}'..='\u{E007E}')
+}
+
+/// Check if a byte offset in `s` falls on an extended grapheme cluster boundary
+pub(crate) fn is_grapheme_boundary(s: &str, offset: usize) -> bool {
+    GraphemeCursor::new(offset, s.len(), true)
+        .is_boundary(s, 0)
+        .unwrap_or(true)
+}
+
+/// Validate that a separator is an emoji or emoji sequence
+///
+/// This applies the default `SeparatorPolicy::StrictEmoji`; use
+/// `SeparatorPolicy::validate` to check against another policy.
 ///
 /// # Errors
 ///
-/// Returns `EsvError::InvalidSeparator` if the character is not an emoji.
-pub fn validate_separator(separator: char) -> Result<(), EsvError> {
-    if is_emoji(separator) {
-        Ok(())
-    } else {
-        Err(EsvError::InvalidSeparator { separator })
+/// Returns `EsvError::InvalidSeparator` if the separator is not a single emoji
+/// grapheme cluster.
+pub fn validate_separator(separator: &str) -> Result<(), EsvError> {
+    SeparatorPolicy::StrictEmoji.validate(separator)
+}
+
+/// Where a record was found in the input
+///
+/// Lines are physical lines (1-based), so a record with a quoted field spanning
+/// several lines covers `line..=end_line`. The record's text, without its line break,
+/// is `&input[offset..offset + len]`, so a stored position is enough to seek back to
+/// the record later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EsvPosition {
+    /// Index of the record among the data records (0-based, excluding any header)
+    pub index: usize,
+    /// Line the record starts on
+    pub line: usize,
+    /// Line the record ends on
+    pub end_line: usize,
+    /// Byte offset of the start of the record
+    pub offset: usize,
+    /// Length of the record in bytes, excluding its line break
+    pub len: usize,
+}
+
+impl EsvPosition {
+    /// Span covering the record's text
+    pub(crate) fn span(&self) -> Span {
+        Span {
+            start: self.offset,
+            end: self.offset + self.len,
+            grapheme_column: 1,
+        }
+    }
+
+    /// Error for a record at this position with `found` fields instead of `expected`
+    pub(crate) fn field_count_error(&self, expected: usize, found: usize) -> EsvError {
+        EsvError::InconsistentFieldCount {
+            expected,
+            found,
+            line: self.line,
+            end_line: self.end_line,
+            span: self.span(),
+        }
     }
 }
 
@@ -120,6 +263,9 @@ pub struct EsvDocument {
     pub headers: Option<Vec<String>>,
     /// Data records
     pub records: Vec<Vec<String>>,
+    /// Position of each data record, if the parser was configured to track them
+    /// with `with_positions(true)`
+    pub positions: Option<Vec<EsvPosition>>,
 }
 
 impl EsvDocument {
@@ -129,6 +275,7 @@ impl EsvDocument {
         Self {
             headers: None,
             records,
+            positions: None,
         }
     }
 
@@ -138,6 +285,7 @@ impl EsvDocument {
         Self {
             headers: Some(headers),
             records,
+            positions: None,
         }
     }
 
@@ -162,6 +310,74 @@ impl EsvDocument {
     }
 }
 
+/// A parsed ESV document whose fields borrow from the input where possible
+///
+/// Returned by `EsvParser::parse_borrowed`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EsvBorrowedDocument<'a> {
+    /// Optional header row
+    pub headers: Option<Vec<Cow<'a, str>>>,
+    /// Data records
+    pub records: Vec<Vec<Cow<'a, str>>>,
+    /// Position of each data record, if the parser was configured to track them
+    pub positions: Option<Vec<EsvPosition>>,
+}
+
+impl<'a> EsvBorrowedDocument<'a> {
+    /// Create a new borrowed document without headers
+    #[must_use]
+    pub fn new(records: Vec<Vec<Cow<'a, str>>>) -> Self {
+        Self {
+            headers: None,
+            records,
+            positions: None,
+        }
+    }
+
+    /// Create a new borrowed document with headers
+    #[must_use]
+    pub fn with_headers(headers: Vec<Cow<'a, str>>, records: Vec<Vec<Cow<'a, str>>>) -> Self {
+        Self {
+            headers: Some(headers),
+            records,
+            positions: None,
+        }
+    }
+
+    /// Returns the number of records (excluding headers)
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns true if there are no records
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Copy every field into an owned `EsvDocument`
+    #[must_use]
+    pub fn into_owned(self) -> EsvDocument {
+        EsvDocument {
+            headers: self.headers.map(into_owned_record),
+            records: self.records.into_iter().map(into_owned_record).collect(),
+            positions: self.positions,
+        }
+    }
+}
+
+/// Convert a record of borrowed fields into owned strings
+pub(crate) fn into_owned_record(record: Vec<Cow<'_, str>>) -> Vec<String> {
+    record.into_iter().map(Cow::into_owned).collect()
+}
+
+/// Whether a record that ended at EOF is the empty record after a trailing line break,
+/// which is dropped rather than read as a record with one empty field
+pub(crate) fn is_trailing_empty<S: AsRef<str>>(record: &[S], ended_at_eof: bool) -> bool {
+    ended_at_eof && (record.is_empty() || (record.len() == 1 && record[0].as_ref().is_empty()))
+}
+
 /// Parse ESV data from a string using the default separator
 ///
 /// # Errors
@@ -313,42 +529,125 @@ mod tests {
         assert!(!is_emoji('£'));
     }
 
+    #[test]
+    fn test_is_emoji_rejects_non_emoji_in_symbol_blocks() {
+        // Arrows: only a handful (↔ ↩ ...) are emoji
+        assert!(!is_emoji('←'));
+        assert!(!is_emoji('⇒'));
+        assert!(is_emoji('↔'));
+        // CJK symbols and punctuation: only 〰 and 〽 are emoji
+        assert!(!is_emoji('、'));
+        assert!(!is_emoji('〃'));
+        assert!(is_emoji('〰'));
+        // Box drawing, block elements and geometric shapes
+        assert!(!is_emoji('─'));
+        assert!(!is_emoji('█'));
+        assert!(!is_emoji('■'));
+        assert!(is_emoji('▶'));
+        // Miscellaneous symbols and dingbats outside the emoji set
+        assert!(!is_emoji('☐'));
+        assert!(!is_emoji('❘'));
+        // Emoji outside the old hard-coded ranges
+        assert!(is_emoji('©'));
+        assert!(is_emoji('‼'));
+        assert!(is_emoji('™'));
+    }
+
+    #[test]
+    fn test_is_emoji_presentation() {
+        assert!(is_emoji_presentation('🔥'));
+        assert!(is_emoji_presentation('⭐'));
+        assert!(!is_emoji_presentation('❤'));
+        assert!(!is_emoji_presentation('✂'));
+        assert!(!is_emoji_presentation('a'));
+    }
+
+    #[test]
+    fn test_unicode_version() {
+        assert!(UNICODE_VERSION >= (15, 0, 0));
+    }
+
     #[test]
     fn test_validate_separator_valid() {
-        assert!(validate_separator('🔥').is_ok());
-        assert!(validate_separator('😀').is_ok());
-        assert!(validate_separator('🚀').is_ok());
-        assert!(validate_separator('⭐').is_ok());
+        assert!(validate_separator("🔥").is_ok());
+        assert!(validate_separator("😀").is_ok());
+        assert!(validate_separator("🚀").is_ok());
+        assert!(validate_separator("⭐").is_ok());
     }
 
     #[test]
     fn test_validate_separator_invalid() {
-        let result = validate_separator(',');
+        let result = validate_separator(",");
         assert!(matches!(
             result,
-            Err(EsvError::InvalidSeparator { separator: ',' })
+            Err(EsvError::InvalidSeparator { separator, .. }) if separator == ","
         ));
 
-        let result = validate_separator('\t');
+        let result = validate_separator("\t");
         assert!(matches!(
             result,
-            Err(EsvError::InvalidSeparator { separator: '\t' })
+            Err(EsvError::InvalidSeparator { separator, .. }) if separator == "\t"
         ));
 
-        let result = validate_separator('|');
+        let result = validate_separator("|");
         assert!(matches!(
             result,
-            Err(EsvError::InvalidSeparator { separator: '|' })
+            Err(EsvError::InvalidSeparator { separator, .. }) if separator == "|"
         ));
     }
 
+    #[test]
+    fn test_validate_separator_emoji_sequences() {
+        // ZWJ sequences
+        assert!(validate_separator("🏳️‍🌈").is_ok());
+        assert!(validate_separator("❤️‍🔥").is_ok());
+        assert!(validate_separator("👩🏽‍💻").is_ok());
+        // Flags
+        assert!(validate_separator("🇺🇸").is_ok());
+        assert!(validate_separator("🇯🇵").is_ok());
+        // Skin tones
+        assert!(validate_separator("👍🏽").is_ok());
+        // Emoji presentation selector
+        assert!(validate_separator("❤️").is_ok());
+        // Keycaps
+        assert!(validate_separator("1️⃣").is_ok());
+        assert!(validate_separator("#⃣").is_ok());
+        // Tag sequences (England flag)
+        assert!(
+            validate_separator("🏴\u{E0067}\u{E0062}\u{E0065}\u{E006E}\u{E0067}\u{E007F}").is_ok()
+        );
+    }
+
+    #[test]
+    fn test_validate_separator_invalid_sequences() {
+        // Empty
+        assert!(validate_separator("").is_err());
+        // More than one grapheme cluster
+        assert!(validate_separator("🔥🔥").is_err());
+        assert!(validate_separator("🇺🇸🇯🇵").is_err());
+        // Lone regional indicator
+        assert!(validate_separator("🇺").is_err());
+        // Bare digit without keycap
+        assert!(validate_separator("1").is_err());
+        // Emoji combined with non-emoji text
+        assert!(validate_separator("🔥a").is_err());
+        assert!(validate_separator("e\u{301}").is_err());
+        // Dangling joiner
+        assert!(validate_separator("🔥\u{200D}").is_err());
+        // Skin tone on an emoji that is not a modifier base
+        assert!(validate_separator("🔥🏽").is_err());
+        // Non-emoji symbols
+        assert!(validate_separator("←").is_err());
+        assert!(validate_separator("█").is_err());
+    }
+
     #[test]
     fn test_parser_rejects_ascii_separator() {
         let parser = EsvParser::new().with_separator(',');
         let result = parser.parse("a,b,c");
         assert!(matches!(
             result,
-            Err(EsvError::InvalidSeparator { separator: ',' })
+            Err(EsvError::InvalidSeparator { separator, .. }) if separator == ","
         ));
     }
 
@@ -368,7 +667,7 @@ mod tests {
         let result = serializer.try_serialize(&doc);
         assert!(matches!(
             result,
-            Err(EsvError::InvalidSeparator { separator: ',' })
+            Err(EsvError::InvalidSeparator { separator, .. }) if separator == ","
         ));
     }
 
@@ -380,4 +679,46 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "a😀b\n");
     }
+
+    #[test]
+    fn test_roundtrip_grapheme_clusters() {
+        let doc = EsvDocument::new(vec![
+            vec!["❤️‍🔥".to_string(), "🔥".to_string(), "🏽tone".to_string()],
+            vec![
+                "e\u{301}".to_string(),
+                "a\u{200D}".to_string(),
+                "\u{301}".to_string(),
+            ],
+        ]);
+        let serialized = EsvSerializer::new().serialize(&doc);
+        assert_eq!(parse(&serialized).unwrap(), doc);
+
+        let serialized = EsvSerializer::new()
+            .with_separator("🇺🇸")
+            .serialize(&EsvDocument::new(vec![vec![
+                "🇯".to_string(),
+                "🇵x".to_string(),
+            ]]));
+        let reparsed = EsvParser::new()
+            .with_separator("🇺🇸")
+            .parse(&serialized)
+            .unwrap();
+        assert_eq!(reparsed.records, vec![vec!["🇯", "🇵x"]]);
+    }
+
+    #[test]
+    fn test_roundtrip_multi_codepoint_separator() {
+        let doc = EsvDocument::with_headers(
+            vec!["flag".to_string(), "note".to_string()],
+            vec![vec!["🇺🇸".to_string(), "has 🏳️‍🌈 inside".to_string()]],
+        );
+        let serialized = EsvSerializer::new().with_separator("🏳️‍🌈").serialize(&doc);
+        assert_eq!(serialized, "flag🏳️‍🌈note\n🇺🇸🏳️‍🌈\"has 🏳️‍🌈 inside\"\n");
+        let reparsed = EsvParser::new()
+            .with_separator("🏳️‍🌈")
+            .with_headers(true)
+            .parse(&serialized)
+            .unwrap();
+        assert_eq!(reparsed, doc);
+    }
 }
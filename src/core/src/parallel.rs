@@ -0,0 +1,489 @@
+//! Parallel chunked parsing
+//!
+//! Large inputs are split after line breaks and the chunks are parsed on worker
+//! threads, each assuming its chunk starts a record. When stitching the chunks back
+//! together in order, a chunk whose assumed start turns out to lie inside a quoted
+//! field is re-parsed from the true record boundary, so results and error positions
+//! always match `EsvParser::parse`.
+
+use std::panic;
+use std::thread;
+
+use memchr::memchr2_iter;
+use memchr::memchr_iter;
+
+use crate::error::EsvError;
+use crate::into_owned_record;
+use crate::is_trailing_empty;
+use crate::parser::Cursor;
+use crate::EsvBorrowedDocument;
+use crate::EsvDocument;
+use crate::EsvParser;
+use crate::EsvPosition;
+
+/// Inputs are only split into chunks of at least this many bytes
+const MIN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Records parsed from one chunk of the input
+struct Chunk {
+    /// Byte offset the chunk was parsed from
+    start: usize,
+    /// Byte offset where parsing stopped: the first record boundary at or past the
+    /// chunk end, the end of the input, or the failing record
+    stop: usize,
+    records: Vec<Vec<String>>,
+    /// Record positions (if tracked), with lines relative to the chunk start
+    positions: Vec<EsvPosition>,
+    /// Number of lines advanced, relative to the chunk start
+    lines: usize,
+    /// Field count and (relative) position of the first record
+    first_record: Option<(usize, EsvPosition)>,
+    /// First error in the chunk, with lines relative to the chunk start
+    error: Option<EsvError>,
+}
+
+impl EsvParser {
+    /// Parse ESV data on up to `threads` worker threads
+    ///
+    /// Returns the same document and errors as `parse()`. Inputs too small to gain
+    /// from splitting are parsed on the calling thread; `std::thread::available_parallelism`
+    /// is a good default for `threads`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `parse()`.
+    pub fn parse_parallel(&self, input: &str, threads: usize) -> Result<EsvDocument, EsvError> {
+        let (parser, body_start) = self.resolve_pragma(input)?;
+
+        parser.validate()?;
+
+        let chunks = threads.min((input.len() - body_start) / MIN_CHUNK_SIZE);
+        parser.parse_chunked(input, body_start, chunks)
+    }
+
+    /// Parse records starting at `start`, split into up to `chunks` chunks
+    fn parse_chunked(
+        &self,
+        input: &str,
+        start: usize,
+        chunks: usize,
+    ) -> Result<EsvDocument, EsvError> {
+        let quote = u8::try_from(self.quote).unwrap_or(b'"');
+        let bounds = split_points(input, start, chunks, quote);
+        if bounds.len() <= 2 {
+            return self
+                .parse_body(input, start, None, None)
+                .map(EsvBorrowedDocument::into_owned);
+        }
+
+        let parsed: Vec<Chunk> = thread::scope(|scope| {
+            let handles: Vec<_> = bounds
+                .windows(2)
+                .map(|range| {
+                    let (chunk_start, chunk_end) = (range[0], range[1]);
+                    scope.spawn(move || self.parse_chunk(input, chunk_start, chunk_end))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect()
+        });
+
+        let mut records = Vec::new();
+        let mut positions = Vec::new();
+        let mut expected_field_count = None;
+        let mut line_num = if start > 0 { 2 } else { 1 };
+        let mut offset = start;
+
+        for (chunk, &end) in parsed.into_iter().zip(&bounds[1..]) {
+            // A quoted field in an earlier chunk ran past this whole chunk
+            if offset >= end {
+                continue;
+            }
+            // The chunk was split inside a quoted field; re-parse from the real boundary
+            let mut chunk = if chunk.start == offset {
+                chunk
+            } else {
+                self.parse_chunk(input, offset, end)
+            };
+
+            let shift = line_num - 1;
+            if self.has_headers && records.is_empty() {
+                if let (Some(headers), Some((_, position))) =
+                    (chunk.records.first_mut(), chunk.first_record)
+                {
+                    let text = &input[position.offset..position.offset + position.len];
+                    self.check_header_row(headers, &shift_lines(position, shift), text, None)?;
+                }
+            }
+            // Field count errors name the headers of the document's first record
+            let headers = records
+                .first()
+                .or(chunk.records.first())
+                .filter(|_| self.has_headers);
+            let name_headers = |error: EsvError| match headers {
+                Some(headers) => error.name_headers(headers),
+                None => error,
+            };
+            if let Some((found, position)) = chunk.first_record {
+                match expected_field_count {
+                    None => expected_field_count = Some(found),
+                    Some(expected) if self.rejects_ragged() && found != expected => {
+                        return Err(name_headers(EsvError::InconsistentFieldCount {
+                            expected,
+                            found,
+                            line: position.line + shift,
+                            end_line: position.end_line + shift,
+                            span: position.span(),
+                        }));
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(error) = chunk.error {
+                return Err(name_headers(error.shift_lines(shift)));
+            }
+
+            if let Some(width) = expected_field_count {
+                for record in &mut chunk.records {
+                    self.ragged_policy.fit(record, width);
+                }
+            }
+            records.extend(chunk.records);
+            positions.extend(chunk.positions.into_iter().map(|p| shift_lines(p, shift)));
+            line_num += chunk.lines;
+            offset = chunk.stop;
+        }
+
+        // Handle headers if specified
+        let mut document = if self.has_headers && !records.is_empty() {
+            let headers = records.remove(0);
+            if self.positions {
+                positions.remove(0);
+            }
+            EsvDocument::with_headers(headers, records)
+        } else {
+            EsvDocument::new(records)
+        };
+        if self.positions {
+            for (index, position) in positions.iter_mut().enumerate() {
+                position.index = index;
+            }
+            document.positions = Some(positions);
+        }
+        Ok(document)
+    }
+
+    /// Parse whole records from `start` until reaching `end` (or the end of the input
+    /// for the last chunk)
+    ///
+    /// Strict field counts are checked against the chunk's first record; the caller
+    /// checks that record against the document.
+    fn parse_chunk(&self, input: &str, start: usize, end: usize) -> Chunk {
+        let mut chunk = Chunk {
+            start,
+            stop: start,
+            records: Vec::new(),
+            positions: Vec::new(),
+            lines: 0,
+            first_record: None,
+            error: None,
+        };
+        let mut cursor = Cursor::new(input);
+        cursor.advance(start);
+        let mut line_num = 1;
+
+        loop {
+            if self.skip_ignored_lines(&mut cursor, &mut line_num) && cursor.offset() == input.len()
+            {
+                break;
+            }
+            let record_start = cursor.offset();
+            let record_line = line_num;
+            let (record, ended_at_eof) = match self.parse_record(&mut cursor, &mut line_num, None) {
+                Ok(result) => result,
+                Err(e) => {
+                    chunk.error = Some(e);
+                    break;
+                }
+            };
+            let position = cursor.record_position(record_start, record_line, line_num);
+            // Don't add empty records at the end (trailing newline)
+            let is_trailing_empty = is_trailing_empty(&record, ended_at_eof);
+
+            match chunk.first_record {
+                _ if is_trailing_empty => {}
+                None => chunk.first_record = Some((record.len(), position)),
+                Some((expected, ..)) if self.rejects_ragged() && record.len() != expected => {
+                    chunk.error = Some(position.field_count_error(expected, record.len()));
+                    break;
+                }
+                _ => {}
+            }
+
+            if !is_trailing_empty {
+                chunk.records.push(into_owned_record(record));
+                if self.positions {
+                    chunk.positions.push(position);
+                }
+            }
+
+            // The last chunk runs to EOF so the trailing record is handled like `parse()`
+            if ended_at_eof || (end < input.len() && cursor.offset() >= end) {
+                break;
+            }
+        }
+
+        chunk.stop = cursor.offset();
+        chunk.lines = line_num - 1;
+        chunk
+    }
+}
+
+/// Move a position parsed from a chunk down by the lines before the chunk
+fn shift_lines(position: EsvPosition, shift: usize) -> EsvPosition {
+    EsvPosition {
+        line: position.line + shift,
+        end_line: position.end_line + shift,
+        ..position
+    }
+}
+
+/// Pick up to `chunks` chunk start offsets, followed by the end of the input
+///
+/// Each split lands just past a line break preceded by an even number of `quote`
+/// bytes, so it is outside any quoted field unless the input has bare quotes in
+/// unquoted fields or backslash-escaped quotes (which stitching detects and repairs).
+fn split_points(input: &str, start: usize, chunks: usize, quote: u8) -> Vec<usize> {
+    let bytes = input.as_bytes();
+    let len = bytes.len() - start;
+    let mut points = vec![start];
+    let mut pos = start;
+    let mut in_quotes = false;
+
+    for i in 1..chunks {
+        let target = start + len * i / chunks;
+        if target > pos {
+            in_quotes ^= memchr_iter(quote, &bytes[pos..target]).count() % 2 == 1;
+            pos = target;
+        }
+
+        let mut split = None;
+        for index in memchr2_iter(quote, b'\n', &bytes[pos..]) {
+            if bytes[pos + index] == quote {
+                in_quotes = !in_quotes;
+            } else if !in_quotes {
+                split = Some(pos + index + 1);
+                break;
+            }
+        }
+        match split {
+            Some(point) if point < bytes.len() => {
+                points.push(point);
+                pos = point;
+            }
+            _ => break,
+        }
+    }
+
+    points.push(bytes.len());
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use super::*;
+    use crate::EscapeStyle;
+    use crate::HeaderPolicy;
+    use crate::RaggedPolicy;
+
+    /// Parse with every chunk count from 2 to 8 and compare against `parse()`
+    fn assert_matches_sequential(parser: &EsvParser, input: &str) {
+        let expected = parser.parse(input);
+        let (resolved, start) = parser.resolve_pragma(input).unwrap();
+        for chunks in 2..=8 {
+            assert_eq!(
+                resolved.parse_chunked(input, start, chunks),
+                expected,
+                "chunks: {chunks}, input: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_points() {
+        let input = "aa\nbb\ncc\ndd\n";
+        assert_eq!(split_points(input, 0, 1, b'"'), vec![0, 12]);
+        assert_eq!(split_points(input, 0, 2, b'"'), vec![0, 9, 12]);
+        assert_eq!(split_points(input, 0, 4, b'"'), vec![0, 6, 9, 12]);
+        assert_eq!(split_points("no line breaks", 0, 4, b'"'), vec![0, 14]);
+
+        // Line breaks inside quoted fields are skipped
+        let input = "\"a\nb\"\nc\nd\n";
+        assert_eq!(split_points(input, 0, 2, b'"'), vec![0, 6, 10]);
+        assert_eq!(split_points(input, 0, 4, b'"'), vec![0, 6, 8, 10]);
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let parser = EsvParser::new();
+        for input in [
+            "a🔥b\nc🔥d\ne🔥f\ng🔥h\ni🔥j\nk🔥l\n",
+            "a🔥b\r\nc🔥d\r\n\r\ne🔥f\r\ng🔥h",
+            "x\ny\nz",
+            "\n\n\n\n",
+        ] {
+            assert_matches_sequential(&parser, input);
+        }
+    }
+
+    #[test]
+    fn test_parallel_quoted_fields_across_chunks() {
+        let parser = EsvParser::new();
+        let input = "\"a\nb\nc\nd\"🔥1\n\"e\n\"\"f\"\"\ng\"🔥2\nh🔥\"\ni\nj\nk\nl\n\"\nm🔥3\n";
+        assert_matches_sequential(&parser, input);
+
+        assert_matches_sequential(&parser.clone().with_positions(true), input);
+
+        // A quoted field swallowing most of the input
+        let input = format!("a🔥\"{}\"\nb🔥c\n", "x\n".repeat(20));
+        assert_matches_sequential(&parser, &input);
+    }
+
+    #[test]
+    fn test_parallel_headers_and_pragma() {
+        let input = "#!esv sep=🌟 header=true\nname🌟age\n\"Al\nice\"🌟30\nBob🌟25\nCy🌟40\n";
+        assert_matches_sequential(&EsvParser::new(), input);
+        assert_matches_sequential(&EsvParser::new().with_headers(true), "h1🔥h2\na🔥b\nc🔥d\n");
+        assert_matches_sequential(
+            &EsvParser::new().with_positions(true),
+            "#!esv header=true\nh1🔥h2\n\"a\nb\"🔥c\nd🔥e\n",
+        );
+
+        let input = "#!esv header=true\nh🔥h\na🔥b\nc🔥d\ne🔥f\n";
+        for policy in [HeaderPolicy::Reject, HeaderPolicy::Rename] {
+            let parser = EsvParser::new().with_header_policy(policy);
+            assert_matches_sequential(&parser, input);
+            assert_matches_sequential(&parser.with_strict_field_count(true), "h🔥h\na\nb\nc\n");
+        }
+    }
+
+    #[test]
+    fn test_parallel_ragged_policy() {
+        let input = "a🔥b🔥c\nd\ne🔥f🔥g🔥h\n\ni🔥j\nk🔥l🔥m\n";
+        for policy in [
+            RaggedPolicy::pad_empty(),
+            RaggedPolicy::Pad("-".into()),
+            RaggedPolicy::Truncate,
+        ] {
+            let parser = EsvParser::new().with_ragged_policy(policy);
+            assert_matches_sequential(&parser, input);
+            assert_matches_sequential(&parser.with_headers(true), input);
+        }
+        let parser = EsvParser::new().with_ragged_policy(RaggedPolicy::Error);
+        assert!(parser.parse(input).is_err());
+        assert_matches_sequential(&parser, input);
+    }
+
+    #[test]
+    fn test_parallel_comments_and_blank_lines() {
+        let parser = EsvParser::new()
+            .with_comment_prefix("#")
+            .with_skip_blank_lines(true)
+            .with_strict_field_count(true)
+            .with_positions(true);
+        for input in [
+            "# a\nb🔥c\n\n# \"d\ne🔥f\n\n\n\"#g\"🔥h\n# i",
+            "# a\n# b\n# c\n# d\nx🔥y\n# e\n# f\n# g\n# h\n",
+            "a🔥\"b\n# c\n\nd\"\n# e\nf🔥g\n\n",
+        ] {
+            assert!(parser.parse(input).is_ok(), "input: {input:?}");
+            assert_matches_sequential(&parser, input);
+        }
+    }
+
+    #[test]
+    fn test_parallel_quote_and_escape_style() {
+        let input = "'a\nb'🔥\"c\nd\n'e''\nf'🔥g\nh\ni\n'j\n'\n";
+        assert_matches_sequential(&EsvParser::new().with_quote('\''), input);
+
+        // Escaped quotes throw off the quote parity used for splitting
+        let parser = EsvParser::new().with_escape_style(EscapeStyle::Backslash);
+        let input = "\"a\\\"\nb\"\nc\nd\n\"\\\"e\nf\\\\\"\ng\nh\ni\nj\n";
+        assert!(parser.parse(input).is_ok());
+        assert_matches_sequential(&parser, input);
+    }
+
+    #[test]
+    fn test_parallel_bare_quotes() {
+        // Bare quotes in unquoted fields throw off the quote parity used for splitting
+        let parser = EsvParser::new();
+        assert_matches_sequential(&parser, "a\"b\nc\nd\n\"e\nf\"\ng\nh\ni\n");
+    }
+
+    #[test]
+    fn test_parallel_errors_match_sequential() {
+        let parser = EsvParser::new();
+        for input in [
+            "a\nb\nc\n\"d\ne\nf\ng\n",
+            "a\nb\nc\nd\ne\n\"f\"x\ng\n",
+            "\"a\n\"\"b\"x\nc\nd\ne\nf\n",
+            "#!esv\na\nb\nc\n\"d\"e\n",
+        ] {
+            assert!(parser.parse(input).is_err());
+            assert_matches_sequential(&parser, input);
+        }
+
+        let parser = EsvParser::new().with_strict_field_count(true);
+        for input in [
+            "a🔥b\nc🔥d\ne🔥f\ng\nh🔥i\nj🔥k",
+            "a🔥b\nc🔥d\ne🔥f\ng🔥h\ni🔥j\nk",
+            "a\nb🔥c\nd\ne\nf\ng",
+        ] {
+            assert!(parser.parse(input).is_err());
+            assert_matches_sequential(&parser, input);
+        }
+
+        // The empty record after a final newline is not checked against the field count
+        let input = "a🔥b\nc🔥d\ne🔥f\ng🔥h\n";
+        assert_eq!(parser.parse_parallel(input, 4).unwrap().records.len(), 4);
+        assert_matches_sequential(&parser, input);
+
+        let parser = parser.with_headers(true);
+        for input in [
+            "x🔥y\nc🔥d\ne🔥f\ng\nh🔥i\nj🔥k",
+            "x🔥y\nc🔥d\ne🔥f\ng🔥h\ni🔥j\nk🔥l🔥m",
+            "x\nb🔥c\nd\ne\nf\ng",
+        ] {
+            assert!(matches!(
+                parser.parse(input),
+                Err(EsvError::HeaderMismatch { .. })
+            ));
+            assert_matches_sequential(&parser, input);
+        }
+    }
+
+    #[test]
+    fn test_parse_parallel_large_input() {
+        let mut input = String::from("id🔥text\n");
+        for i in 0..20_000 {
+            let _ = write!(input, "{i}🔥\"multi\nline \"\"{i}\"\"\"\n");
+        }
+        let parser = EsvParser::new().with_headers(true);
+        let doc = parser.parse_parallel(&input, 4).unwrap();
+        assert_eq!(doc.len(), 20_000);
+        assert_eq!(doc, parser.parse(&input).unwrap());
+
+        input.push_str("\"unclosed\n");
+        assert_eq!(parser.parse_parallel(&input, 4), parser.parse(&input));
+    }
+
+    #[test]
+    fn test_parse_parallel_invalid_separator() {
+        let parser = EsvParser::new().with_separator(',');
+        assert_eq!(parser.parse_parallel("a,b", 4), parser.parse("a,b"));
+    }
+}
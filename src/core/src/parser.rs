@@ -3,17 +3,71 @@
 //! Parses ESV (Emoji Separated Values) data following RFC 4180 conventions
 //! adapted for emoji separators.
 
+use std::borrow::Cow;
+
+use memchr::memchr2;
+use memchr::memchr3;
+
+use crate::error::columns;
+use crate::error::line_start;
 use crate::error::EsvError;
-use crate::validate_separator;
+use crate::error::Span;
+use crate::is_grapheme_boundary;
+use crate::is_trailing_empty;
+use crate::quoting::{validate_quote, DEFAULT_QUOTE};
+use crate::warning::line_break_name;
+use crate::EscapeStyle;
+use crate::EsvBorrowedDocument;
+use crate::EsvDialect;
 use crate::EsvDocument;
+use crate::EsvPosition;
+use crate::EsvPragma;
+use crate::EsvWarning;
+use crate::HeaderPolicy;
+use crate::PragmaMode;
+use crate::RaggedPolicy;
+use crate::SeparatorPolicy;
 use crate::DEFAULT_SEPARATOR;
 
+/// Outcome of `EsvParser::parse_lenient`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseReport {
+    /// The records that parsed cleanly
+    pub document: EsvDocument,
+    /// Every error found, in input order
+    pub errors: Vec<EsvError>,
+    /// Input that parsed but does not conform to the spec, in input order
+    pub warnings: Vec<EsvWarning>,
+}
+
+impl ParseReport {
+    /// Returns true if no errors were found
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
 /// Parser for ESV data
+// Each flag is an independent builder option
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone)]
 pub struct EsvParser {
-    separator: char,
-    has_headers: bool,
-    strict_field_count: bool,
+    pub(crate) separator: String,
+    pub(crate) separator_policy: SeparatorPolicy,
+    pub(crate) has_headers: bool,
+    pub(crate) strict_field_count: bool,
+    pub(crate) pragma_mode: PragmaMode,
+    pub(crate) lossy_utf8: bool,
+    pub(crate) positions: bool,
+    pub(crate) strict_syntax: bool,
+    pub(crate) preserve_line_endings: bool,
+    pub(crate) header_policy: HeaderPolicy,
+    pub(crate) ragged_policy: RaggedPolicy,
+    pub(crate) comment_prefix: Option<String>,
+    pub(crate) skip_blank_lines: bool,
+    pub(crate) quote: char,
+    pub(crate) escape_style: EscapeStyle,
 }
 
 impl Default for EsvParser {
@@ -27,19 +81,59 @@ impl EsvParser {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            separator: DEFAULT_SEPARATOR,
+            separator: DEFAULT_SEPARATOR.to_string(),
+            separator_policy: SeparatorPolicy::StrictEmoji,
             has_headers: false,
             strict_field_count: false,
+            pragma_mode: PragmaMode::Detect,
+            lossy_utf8: false,
+            positions: false,
+            strict_syntax: false,
+            preserve_line_endings: false,
+            header_policy: HeaderPolicy::Accept,
+            ragged_policy: RaggedPolicy::Keep,
+            comment_prefix: None,
+            skip_blank_lines: false,
+            quote: DEFAULT_QUOTE,
+            escape_style: EscapeStyle::Doubled,
+        }
+    }
+
+    /// Create a parser configured with the settings of a dialect
+    #[must_use]
+    pub fn from_dialect(dialect: &EsvDialect) -> Self {
+        Self {
+            separator: dialect.separator.clone(),
+            separator_policy: dialect.separator_policy.clone(),
+            has_headers: dialect.has_headers,
+            strict_field_count: dialect.strict_field_count,
+            quote: dialect.quote,
+            escape_style: dialect.escape_style,
+            comment_prefix: dialect.comment_prefix.clone().filter(|p| !p.is_empty()),
+            skip_blank_lines: dialect.skip_blank_lines,
+            ..Self::new()
         }
     }
 
     /// Set a custom emoji separator
     ///
+    /// The separator may be a single emoji (`'😀'`) or a full emoji sequence such as
+    /// a ZWJ sequence, flag or skin-tone variant (`"🏳️‍🌈"`, `"🇺🇸"`, `"👍🏽"`).
+    ///
     /// Note: The separator will be validated when `parse()` is called.
-    /// Only emoji characters are allowed as separators.
+    /// Only emoji are allowed as separators.
+    #[must_use]
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Set the policy deciding which separators are accepted
+    ///
+    /// Defaults to `SeparatorPolicy::StrictEmoji`.
     #[must_use]
-    pub fn with_separator(mut self, separator: char) -> Self {
-        self.separator = separator;
+    pub fn with_separator_policy(mut self, policy: SeparatorPolicy) -> Self {
+        self.separator_policy = policy;
         self
     }
 
@@ -50,58 +144,411 @@ impl EsvParser {
         self
     }
 
+    /// Set how duplicate, empty and whitespace-padded header names are handled
+    ///
+    /// Defaults to `HeaderPolicy::Accept`. Only applies with `with_headers(true)`.
+    #[must_use]
+    pub fn with_header_policy(mut self, policy: HeaderPolicy) -> Self {
+        self.header_policy = policy;
+        self
+    }
+
     /// Enable strict field count validation (all rows must have same number of fields)
+    ///
+    /// With headers, records are checked against the header row and errors name the
+    /// headers a record has no field for (`EsvError::HeaderMismatch`).
     #[must_use]
     pub fn with_strict_field_count(mut self, strict: bool) -> Self {
         self.strict_field_count = strict;
         self
     }
 
+    /// Set how records with more or fewer fields than the first record are handled
+    ///
+    /// Defaults to `RaggedPolicy::Keep`. Records are padded or truncated to the width
+    /// of the header row (or the first record without headers); `RaggedPolicy::Error`
+    /// behaves like `with_strict_field_count(true)`.
+    #[must_use]
+    pub fn with_ragged_policy(mut self, policy: RaggedPolicy) -> Self {
+        self.ragged_policy = policy;
+        self
+    }
+
+    /// Skip lines starting with `prefix` (such as `#`) as comments
+    ///
+    /// Only the start of a record is checked, so a quoted field beginning with the
+    /// prefix (`"# tag"`) and lines inside a quoted field are data. A pragma line is
+    /// resolved before comments are skipped. Disabled by default; an empty prefix
+    /// disables it.
+    #[must_use]
+    pub fn with_comment_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.comment_prefix = Some(prefix.into()).filter(|prefix| !prefix.is_empty());
+        self
+    }
+
+    /// Skip empty lines between records instead of parsing them as records with one
+    /// empty field
+    ///
+    /// Disabled by default.
+    #[must_use]
+    pub fn with_skip_blank_lines(mut self, skip: bool) -> Self {
+        self.skip_blank_lines = skip;
+        self
+    }
+
+    /// Set how a leading `#!esv` pragma line is handled
+    ///
+    /// Defaults to `PragmaMode::Detect`, where a pragma overrides the configured
+    /// separator and header setting.
+    #[must_use]
+    pub fn with_pragma_mode(mut self, mode: PragmaMode) -> Self {
+        self.pragma_mode = mode;
+        self
+    }
+
+    /// Replace invalid UTF-8 sequences with U+FFFD instead of failing
+    ///
+    /// Applies to `parse_bytes()` and to `EsvReader`. Disabled by default.
+    #[must_use]
+    pub fn with_lossy_utf8(mut self, lossy: bool) -> Self {
+        self.lossy_utf8 = lossy;
+        self
+    }
+
+    /// Record the position of each record in `EsvDocument::positions`
+    ///
+    /// Disabled by default.
+    #[must_use]
+    pub fn with_positions(mut self, positions: bool) -> Self {
+        self.positions = positions;
+        self
+    }
+
+    /// Reject double quotes inside unquoted fields (`a"b`) with `EsvError::BareQuote`
+    ///
+    /// RFC 4180 only allows quotes in quoted fields. Disabled by default, where a bare
+    /// quote is kept as a literal character (and reported as a warning by
+    /// `parse_lenient()`).
+    #[must_use]
+    pub fn with_strict_syntax(mut self, strict: bool) -> Self {
+        self.strict_syntax = strict;
+        self
+    }
+
+    /// Keep CR and CRLF line breaks inside quoted fields byte-for-byte
+    ///
+    /// Disabled by default, where they are normalized to LF.
+    #[must_use]
+    pub fn with_preserve_line_endings(mut self, preserve: bool) -> Self {
+        self.preserve_line_endings = preserve;
+        self
+    }
+
+    /// Set the character that encloses quoted fields (default: `"`)
+    ///
+    /// The quote must be ASCII punctuation that is neither part of the separator nor
+    /// the start of the comment prefix; it is validated when parsing.
+    #[must_use]
+    pub fn with_quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Set how quotes are escaped inside quoted fields
+    ///
+    /// Defaults to `EscapeStyle::Doubled`.
+    #[must_use]
+    pub fn with_escape_style(mut self, escape_style: EscapeStyle) -> Self {
+        self.escape_style = escape_style;
+        self
+    }
+
+    /// The configured separator
+    #[must_use]
+    pub fn separator(&self) -> &str {
+        &self.separator
+    }
+
     /// Parse ESV data from a string
     ///
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The separator is not an emoji character
+    /// - The separator is rejected by the separator policy
+    /// - The quote character is invalid
+    /// - A pragma line is malformed, or required but missing
     /// - A quoted field is not properly closed
     /// - An unexpected character appears after a closing quote
+    /// - An unquoted field contains a quote (when strict syntax is enabled)
     /// - Field counts are inconsistent (when strict mode is enabled)
+    /// - A header name is empty, padded or repeated (when the header policy rejects
+    ///   them)
     pub fn parse(&self, input: &str) -> Result<EsvDocument, EsvError> {
-        // Validate separator is an emoji
-        validate_separator(self.separator)?;
+        self.parse_borrowed(input)
+            .map(EsvBorrowedDocument::into_owned)
+    }
+
+    /// Parse ESV data from raw bytes
+    ///
+    /// The input must be UTF-8 unless lossy decoding is enabled with
+    /// `with_lossy_utf8()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::InvalidUtf8` with the line, column and byte offset of the
+    /// first invalid byte, or any error returned by `parse()`.
+    pub fn parse_bytes(&self, input: &[u8]) -> Result<EsvDocument, EsvError> {
+        self.parse(&self.decode_utf8(input)?)
+    }
 
-        if input.is_empty() {
-            return Ok(EsvDocument::new(vec![]));
+    /// Decode raw bytes as UTF-8, replacing invalid sequences if lossy decoding is
+    /// enabled
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::InvalidUtf8` with the line, column and byte offset of the
+    /// first invalid byte unless lossy decoding is enabled.
+    pub fn decode_utf8<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, str>, EsvError> {
+        if self.lossy_utf8 {
+            return Ok(String::from_utf8_lossy(input));
         }
+        match std::str::from_utf8(input) {
+            Ok(input) => Ok(Cow::Borrowed(input)),
+            Err(e) => {
+                let valid = e.valid_up_to();
+                // The prefix up to `valid_up_to` is valid UTF-8 by definition
+                let preceding = std::str::from_utf8(&input[..valid]).unwrap_or_default();
+                let len = e.error_len().unwrap_or(input.len() - valid);
+                Err(EsvError::invalid_utf8(preceding, 1, valid, len))
+            }
+        }
+    }
 
-        let mut records = Vec::new();
-        let mut chars = input.chars().peekable();
-        let mut line_num = 1;
+    /// Parse ESV data, also returning warnings about input that does not conform to
+    /// the spec but was accepted anyway (see `EsvWarning`)
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `parse()`.
+    pub fn parse_with_warnings(
+        &self,
+        input: &str,
+    ) -> Result<(EsvDocument, Vec<EsvWarning>), EsvError> {
+        let (parser, body_start) = self.resolve_pragma(input)?;
+
+        // Validate the separator and quote character
+        parser.validate()?;
+
+        let mut warnings = Vec::new();
+        let document = parser
+            .parse_body(input, body_start, None, Some(&mut warnings))?
+            .into_owned();
+        Ok((document, warnings))
+    }
+
+    /// Parse ESV data, collecting errors instead of stopping at the first one
+    ///
+    /// After a malformed record the parser resynchronizes at the next line break (for
+    /// an unclosed quote, the first line break of the record) and keeps going. Records
+    /// with errors, including inconsistent field counts in strict mode, are left out
+    /// of the document.
+    ///
+    /// Warnings are collected as by `parse_with_warnings()`; with strict syntax, bare
+    /// quotes in unquoted fields are errors instead.
+    ///
+    /// # Errors
+    ///
+    /// Only errors that prevent parsing altogether are returned: a separator rejected
+    /// by the separator policy, an invalid quote character, or a malformed or missing
+    /// pragma line.
+    pub fn parse_lenient(&self, input: &str) -> Result<ParseReport, EsvError> {
+        let (parser, body_start) = self.resolve_pragma(input)?;
+
+        // Validate the separator and quote character
+        parser.validate()?;
+
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let document = parser
+            .parse_body(input, body_start, Some(&mut errors), Some(&mut warnings))?
+            .into_owned();
+        Ok(ParseReport {
+            document,
+            errors,
+            warnings,
+        })
+    }
+
+    /// Parse ESV data from a string without copying fields out of the input
+    ///
+    /// Fields borrow from `input` and are only allocated when unescaping `""` or
+    /// normalizing line breaks inside a quoted field changes their text.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `parse()`.
+    pub fn parse_borrowed<'a>(&self, input: &'a str) -> Result<EsvBorrowedDocument<'a>, EsvError> {
+        let (parser, body_start) = self.resolve_pragma(input)?;
+
+        // Validate the separator and quote character
+        parser.validate()?;
+
+        parser.parse_body(input, body_start, None, None)
+    }
+
+    /// Read the pragma line at the start of the input, if any
+    ///
+    /// Honors the configured `PragmaMode`: always `Ok(None)` when ignoring pragmas.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::InvalidPragma` if the pragma is malformed, or
+    /// `EsvError::MissingPragma` if a pragma is required but absent.
+    pub fn detect_pragma(&self, input: &str) -> Result<Option<EsvPragma>, EsvError> {
+        if self.pragma_mode == PragmaMode::Ignore {
+            return Ok(None);
+        }
+        match EsvPragma::split(input) {
+            Some((line, _)) => EsvPragma::parse(line).map(Some),
+            None if self.pragma_mode == PragmaMode::Require => Err(EsvError::MissingPragma),
+            None => Ok(None),
+        }
+    }
+
+    /// Apply a leading pragma, returning the effective parser and the byte offset
+    /// where records start
+    pub(crate) fn resolve_pragma<'a>(
+        &'a self,
+        input: &str,
+    ) -> Result<(Cow<'a, EsvParser>, usize), EsvError> {
+        match self.detect_pragma(input)? {
+            Some(pragma) => {
+                let mut parser = self.clone();
+                parser.apply_pragma(&pragma);
+                let rest = EsvPragma::split(input).map_or("", |(_, rest)| rest);
+                Ok((Cow::Owned(parser), input.len() - rest.len()))
+            }
+            None => Ok((Cow::Borrowed(self), 0)),
+        }
+    }
+
+    /// Override settings with those declared by a pragma
+    pub(crate) fn apply_pragma(&mut self, pragma: &EsvPragma) {
+        if let Some(separator) = &pragma.separator {
+            self.separator.clone_from(separator);
+        }
+        if let Some(has_headers) = pragma.has_headers {
+            self.has_headers = has_headers;
+        }
+    }
+
+    /// Parse records starting at `start` (just past any pragma line)
+    ///
+    /// With `errors`, malformed records are recorded there and skipped instead of
+    /// failing the parse. With `warnings`, spec violations the parser tolerates are
+    /// recorded there.
+    pub(crate) fn parse_body<'a>(
+        &self,
+        input: &'a str,
+        start: usize,
+        mut errors: Option<&mut Vec<EsvError>>,
+        mut warnings: Option<&mut Vec<EsvWarning>>,
+    ) -> Result<EsvBorrowedDocument<'a>, EsvError> {
+        if input.len() == start {
+            return Ok(self.build_document(Vec::new(), Vec::new(), false));
+        }
+
+        let mut records: Vec<Vec<Cow<'a, str>>> = Vec::new();
+        let mut positions = Vec::new();
+        let mut cursor = Cursor::new(input);
+        cursor.advance(start);
+        let mut line_num = if start > 0 { 2 } else { 1 };
         let mut expected_field_count: Option<usize> = None;
+        let mut line_endings = LineEndings::default();
+        // A header row that fails to parse still takes the header slot, so no data
+        // row is promoted in its place
+        let mut header_failed = false;
 
         loop {
-            let (record, ended_at_eof) = self.parse_record(&mut chars, &mut line_num)?;
-
-            // Validate field count if strict mode is enabled
-            if self.strict_field_count {
-                match expected_field_count {
-                    None => expected_field_count = Some(record.len()),
-                    Some(expected) if record.len() != expected => {
-                        return Err(EsvError::InconsistentFieldCount {
-                            expected,
-                            found: record.len(),
-                            line: line_num,
-                        });
+            if self.skip_ignored_lines(&mut cursor, &mut line_num) && cursor.rest().is_empty() {
+                break;
+            }
+            let record_start = cursor.offset();
+            let record_line = line_num;
+            let parsed = self.parse_record(&mut cursor, &mut line_num, warnings.as_deref_mut());
+            let (mut record, ended_at_eof) = match parsed {
+                Ok(result) => result,
+                Err(e) => {
+                    let Some(errors) = errors.as_deref_mut() else {
+                        return Err(e);
+                    };
+                    // Resynchronize at the next line break; an unclosed quote ran to
+                    // EOF, so restart from the line where its record began
+                    if matches!(e, EsvError::UnclosedQuote { .. }) {
+                        cursor.seek(record_start);
+                        line_num = record_line;
+                    }
+                    if self.has_headers && records.is_empty() {
+                        header_failed = true;
+                    }
+                    errors.push(e);
+                    if !cursor.skip_line() {
+                        break;
+                    }
+                    line_num += 1;
+                    continue;
+                }
+            };
+            let position = cursor.record_position(record_start, record_line, line_num);
+            // Don't add empty records at the end (trailing newline)
+            let is_trailing_empty = is_trailing_empty(&record, ended_at_eof);
+
+            // Validate field count if ragged records are rejected
+            match expected_field_count {
+                _ if is_trailing_empty => {}
+                None => expected_field_count = Some(record.len()),
+                Some(expected) if self.rejects_ragged() && record.len() != expected => {
+                    let mut e = position.field_count_error(expected, record.len());
+                    // The first record kept is the header row
+                    if let Some(headers) = records
+                        .first()
+                        .filter(|_| self.has_headers && !header_failed)
+                    {
+                        e = e.name_headers(headers);
+                    }
+                    let Some(errors) = errors.as_deref_mut() else {
+                        return Err(e);
+                    };
+                    errors.push(e);
+                    if ended_at_eof {
+                        break;
                     }
-                    _ => {}
+                    continue;
                 }
+                _ => {}
             }
 
-            // Don't add empty records at the end (trailing newline)
-            let is_trailing_empty =
-                ended_at_eof && (record.is_empty() || (record.len() == 1 && record[0].is_empty()));
+            if let Some(warnings) = warnings.as_deref_mut() {
+                record_end_warnings(
+                    &cursor,
+                    &position,
+                    is_trailing_empty,
+                    &mut line_endings,
+                    warnings,
+                );
+            }
             if !is_trailing_empty {
+                if let Some(width) = expected_field_count {
+                    self.ragged_policy.fit(&mut record, width);
+                }
+                if self.has_headers && !header_failed && records.is_empty() {
+                    let text = &input[position.offset..position.offset + position.len];
+                    self.check_header_row(&mut record, &position, text, errors.as_deref_mut())?;
+                }
                 records.push(record);
+                if self.positions {
+                    positions.push(position);
+                }
             }
 
             if ended_at_eof {
@@ -109,26 +556,145 @@ impl EsvParser {
             }
         }
 
-        // Handle headers if specified
-        if self.has_headers && !records.is_empty() {
+        let has_header_row = self.has_headers && !header_failed;
+        Ok(self.build_document(records, positions, has_header_row))
+    }
+
+    /// Split off the header row if there is one, and attach record positions if enabled
+    fn build_document<'a>(
+        &self,
+        mut records: Vec<Vec<Cow<'a, str>>>,
+        mut positions: Vec<EsvPosition>,
+        has_header_row: bool,
+    ) -> EsvBorrowedDocument<'a> {
+        let mut document = if has_header_row && !records.is_empty() {
             let headers = records.remove(0);
-            Ok(EsvDocument::with_headers(headers, records))
+            if self.positions {
+                positions.remove(0);
+            }
+            EsvBorrowedDocument::with_headers(headers, records)
+        } else {
+            EsvBorrowedDocument::new(records)
+        };
+        if self.positions {
+            for (index, position) in positions.iter_mut().enumerate() {
+                position.index = index;
+            }
+            document.positions = Some(positions);
+        }
+        document
+    }
+
+    /// Validate the separator against the policy, and the quote character
+    pub(crate) fn validate(&self) -> Result<(), EsvError> {
+        self.separator_policy.validate(&self.separator)?;
+        validate_quote(
+            self.quote,
+            self.escape_style,
+            &self.separator,
+            self.comment_prefix.as_deref(),
+        )
+    }
+
+    /// Whether records with a different field count than the first are an error
+    pub(crate) fn rejects_ragged(&self) -> bool {
+        self.strict_field_count || self.ragged_policy == RaggedPolicy::Error
+    }
+
+    /// Apply the header policy to the header row `text` parsed at `position`,
+    /// recording the problems found in `errors` or failing on the first
+    pub(crate) fn check_header_row<S>(
+        &self,
+        headers: &mut [S],
+        position: &EsvPosition,
+        text: &str,
+        errors: Option<&mut Vec<EsvError>>,
+    ) -> Result<(), EsvError>
+    where
+        S: AsRef<str> + From<String>,
+    {
+        // Field spans are only needed to point errors at the offending header
+        let spans = if self.header_policy == HeaderPolicy::Reject {
+            self.field_spans(text, position.offset)
         } else {
-            Ok(EsvDocument::new(records))
+            Vec::new()
+        };
+        let problems = self.header_policy.apply(headers, position, &spans);
+        match errors {
+            Some(errors) => errors.extend(problems),
+            None => {
+                if let Some(e) = problems.into_iter().next() {
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Spans of the fields of the record `text` found at byte offset `offset`,
+    /// including the quotes of quoted fields
+    fn field_spans(&self, text: &str, offset: usize) -> Vec<Span> {
+        let mut cursor = Cursor::new(text);
+        let mut line_num = 1;
+        let mut spans = Vec::new();
+        loop {
+            let start = cursor.offset();
+            let Ok((_, terminator)) = self.parse_field(&mut cursor, &mut line_num, None) else {
+                break;
+            };
+            let end = match terminator {
+                FieldTerminator::Separator => cursor.offset() - self.separator.len(),
+                FieldTerminator::LineBreak | FieldTerminator::Eof => cursor.offset(),
+            };
+            spans.push(Span {
+                start: offset + start,
+                end: offset + end,
+                grapheme_column: cursor.columns(start).1,
+            });
+            if !matches!(terminator, FieldTerminator::Separator) {
+                break;
+            }
+        }
+        spans
+    }
+
+    /// Skip comment lines and (if enabled) blank lines at the start of a record,
+    /// returning whether any were skipped
+    pub(crate) fn skip_ignored_lines(&self, cursor: &mut Cursor<'_>, line_num: &mut usize) -> bool {
+        let mut skipped = false;
+        loop {
+            let rest = cursor.rest();
+            let is_comment = self
+                .comment_prefix
+                .as_deref()
+                .is_some_and(|p| rest.starts_with(p));
+            let is_blank = self.skip_blank_lines && rest.starts_with(['\r', '\n']);
+            if !is_comment && !is_blank {
+                return skipped;
+            }
+            skipped = true;
+            if !cursor.skip_line() {
+                return skipped;
+            }
+            *line_num += 1;
         }
     }
 
-    /// Parse a single record (line) from the input
-    fn parse_record(
+    /// Parse a single record from the input
+    ///
+    /// `line_num` is advanced past every line break consumed, including those inside
+    /// quoted fields.
+    pub(crate) fn parse_record<'a>(
         &self,
-        chars: &mut std::iter::Peekable<std::str::Chars>,
+        cursor: &mut Cursor<'a>,
         line_num: &mut usize,
-    ) -> Result<(Vec<String>, bool), EsvError> {
+        mut warnings: Option<&mut Vec<EsvWarning>>,
+    ) -> Result<(Vec<Cow<'a, str>>, bool), EsvError> {
         let mut fields = Vec::new();
-        let mut column = 1;
 
         loop {
-            let (field, terminator) = self.parse_field(chars, *line_num, &mut column)?;
+            let (field, terminator) =
+                self.parse_field(cursor, line_num, warnings.as_deref_mut())?;
             fields.push(field);
 
             match terminator {
@@ -147,154 +713,684 @@ impl EsvParser {
     }
 
     /// Parse a single field from the input
-    fn parse_field(
+    fn parse_field<'a>(
         &self,
-        chars: &mut std::iter::Peekable<std::str::Chars>,
-        line_num: usize,
-        column: &mut usize,
-    ) -> Result<(String, FieldTerminator), EsvError> {
-        let start_column = *column;
-
+        cursor: &mut Cursor<'a>,
+        line_num: &mut usize,
+        warnings: Option<&mut Vec<EsvWarning>>,
+    ) -> Result<(Cow<'a, str>, FieldTerminator), EsvError> {
         // Check if field is quoted
-        if chars.peek() == Some(&'"') {
-            chars.next(); // consume opening quote
-            *column += 1;
-            self.parse_quoted_field(chars, line_num, start_column, column)
+        if cursor.peek() == Some(self.quote) {
+            cursor.next(); // consume opening quote
+            self.parse_quoted_field(cursor, line_num, warnings)
         } else {
-            self.parse_unquoted_field(chars, column)
+            self.parse_unquoted_field(cursor, *line_num, warnings)
         }
     }
 
     /// Parse a quoted field (handles escaped quotes and embedded separators/newlines)
-    fn parse_quoted_field(
+    ///
+    /// Borrows the field text unless it contains escapes or CR line breaks to
+    /// normalize. The first normalized line break is reported to `warnings`.
+    fn parse_quoted_field<'a>(
         &self,
-        chars: &mut std::iter::Peekable<std::str::Chars>,
-        line_num: usize,
-        start_column: usize,
-        column: &mut usize,
-    ) -> Result<(String, FieldTerminator), EsvError> {
-        let mut field = String::new();
+        cursor: &mut Cursor<'a>,
+        line_num: &mut usize,
+        mut warnings: Option<&mut Vec<EsvWarning>>,
+    ) -> Result<(Cow<'a, str>, FieldTerminator), EsvError> {
+        let start = cursor.offset();
+        let start_line = *line_num;
+        let mut needs_unescape = false;
+        let mut normalized = false;
+
+        // The quote is validated to be ASCII
+        let quote_byte = u8::try_from(self.quote).unwrap_or(b'"');
+        let backslash = self.escape_style == EscapeStyle::Backslash;
 
         loop {
-            match chars.next() {
-                Some('"') => {
-                    *column += 1;
+            // Skip ahead to the next quote, escape or line break
+            if backslash {
+                cursor.skip_until_any(&[quote_byte, b'\\', b'\r', b'\n']);
+            } else {
+                cursor.skip_until(quote_byte, b'\r', b'\n');
+            }
+            match cursor.next() {
+                Some('\\') if backslash => {
+                    // Escape sequence - unescaped once the field is complete. A
+                    // line break after the backslash is left to count its line.
+                    if cursor.peek().is_some_and(|c| c != '\r' && c != '\n') {
+                        cursor.next();
+                    }
+                    needs_unescape = true;
+                }
+                Some(c) if c == self.quote => {
                     // Check if this is an escaped quote or end of field
-                    if chars.peek() == Some(&'"') {
-                        // Escaped quote - add single quote to field
-                        chars.next();
-                        *column += 1;
-                        field.push('"');
+                    if !backslash && cursor.peek() == Some(self.quote) {
+                        // Escaped quote - unescaped once the field is complete
+                        cursor.next();
+                        needs_unescape = true;
                     } else {
-                        // End of quoted field - check what follows
-                        return match chars.peek() {
-                            Some(&c) if c == self.separator => {
-                                chars.next();
-                                *column += 1;
-                                Ok((field, FieldTerminator::Separator))
-                            }
-                            Some('\r') => {
-                                chars.next();
-                                *column += 1;
-                                if chars.peek() == Some(&'\n') {
-                                    chars.next();
-                                }
-                                Ok((field, FieldTerminator::LineBreak))
-                            }
-                            Some('\n') => {
-                                chars.next();
-                                Ok((field, FieldTerminator::LineBreak))
-                            }
-                            None => Ok((field, FieldTerminator::Eof)),
-                            Some(&c) => Err(EsvError::UnexpectedCharAfterQuote {
-                                line: line_num,
-                                column: *column,
-                                found: c,
-                            }),
+                        let raw = cursor.slice(start, cursor.offset() - 1);
+                        let field = if needs_unescape {
+                            Cow::Owned(self.unescape_quoted(raw))
+                        } else {
+                            Cow::Borrowed(raw)
                         };
+                        return self.end_quoted_field(cursor, field, *line_num);
                     }
                 }
                 Some('\r') => {
-                    *column = 1;
-                    if chars.peek() == Some(&'\n') {
-                        chars.next();
+                    let offset = cursor.offset() - 1;
+                    if cursor.peek() == Some('\n') {
+                        cursor.next();
                     }
-                    field.push('\n');
-                }
-                Some('\n') => {
-                    *column = 1;
-                    field.push('\n');
-                }
-                Some(c) => {
-                    *column += 1;
-                    field.push(c);
+                    if !self.preserve_line_endings {
+                        if let Some(warnings) = warnings.as_deref_mut().filter(|_| !normalized) {
+                            let (column, grapheme_column) = cursor.columns(offset);
+                            warnings.push(EsvWarning::NormalizedLineBreak {
+                                line: *line_num,
+                                column,
+                                span: Span {
+                                    start: offset,
+                                    end: cursor.offset(),
+                                    grapheme_column,
+                                },
+                            });
+                            normalized = true;
+                        }
+                        needs_unescape = true;
+                    }
+                    *line_num += 1;
                 }
+                Some('\n') => *line_num += 1,
+                Some(_) => {}
                 None => {
+                    // Point at the opening quote
+                    let (column, grapheme_column) = cursor.columns(start - 1);
                     return Err(EsvError::UnclosedQuote {
-                        line: line_num,
-                        column: start_column,
+                        line: start_line,
+                        column,
+                        span: Span {
+                            start: start - 1,
+                            end: cursor.offset(),
+                            grapheme_column,
+                        },
                     });
                 }
             }
         }
     }
 
+    /// Finish a quoted field after its closing quote by consuming the separator or
+    /// line break that must follow
+    fn end_quoted_field<'a>(
+        &self,
+        cursor: &mut Cursor<'a>,
+        field: Cow<'a, str>,
+        line_num: usize,
+    ) -> Result<(Cow<'a, str>, FieldTerminator), EsvError> {
+        if self.eat_separator(cursor) {
+            return Ok((field, FieldTerminator::Separator));
+        }
+        match cursor.peek() {
+            Some('\r') => {
+                cursor.next();
+                if cursor.peek() == Some('\n') {
+                    cursor.next();
+                }
+                Ok((field, FieldTerminator::LineBreak))
+            }
+            Some('\n') => {
+                cursor.next();
+                Ok((field, FieldTerminator::LineBreak))
+            }
+            None => Ok((field, FieldTerminator::Eof)),
+            Some(c) => {
+                let offset = cursor.offset();
+                let (column, grapheme_column) = cursor.columns(offset);
+                Err(EsvError::UnexpectedCharAfterQuote {
+                    line: line_num,
+                    column,
+                    found: c,
+                    span: Span {
+                        start: offset,
+                        end: offset + c.len_utf8(),
+                        grapheme_column,
+                    },
+                })
+            }
+        }
+    }
+
     /// Parse an unquoted field
-    fn parse_unquoted_field(
+    ///
+    /// A quote inside the field fails with `EsvError::BareQuote` under strict syntax;
+    /// otherwise it is kept and reported to `warnings`. The error leaves the cursor
+    /// before the field's terminator so lenient parsing resynchronizes on its line.
+    fn parse_unquoted_field<'a>(
         &self,
-        chars: &mut std::iter::Peekable<std::str::Chars>,
-        column: &mut usize,
-    ) -> Result<(String, FieldTerminator), EsvError> {
-        let mut field = String::new();
+        cursor: &mut Cursor<'a>,
+        line_num: usize,
+        warnings: Option<&mut Vec<EsvWarning>>,
+    ) -> Result<(Cow<'a, str>, FieldTerminator), EsvError> {
+        let start = cursor.offset();
+        let separator_lead = self.separator.as_bytes().first().copied().unwrap_or(b'\n');
 
-        loop {
-            match chars.peek() {
-                Some(&c) if c == self.separator => {
-                    chars.next();
-                    *column += 1;
-                    return Ok((field, FieldTerminator::Separator));
+        let terminator = loop {
+            // Skip ahead to the next possible separator or line break
+            cursor.skip_until(separator_lead, b'\r', b'\n');
+            match cursor.peek() {
+                Some('\r' | '\n') => break FieldTerminator::LineBreak,
+                None => break FieldTerminator::Eof,
+                Some(_) if self.at_separator(cursor) => break FieldTerminator::Separator,
+                Some(_) => {
+                    cursor.next();
                 }
-                Some('\r') => {
-                    chars.next();
-                    if chars.peek() == Some(&'\n') {
-                        chars.next();
-                    }
-                    return Ok((field, FieldTerminator::LineBreak));
+            }
+        };
+        let field = cursor.slice(start, cursor.offset());
+
+        if self.strict_syntax || warnings.is_some() {
+            if let Some(index) = field.find(self.quote) {
+                let offset = start + index;
+                let (column, grapheme_column) = cursor.columns(offset);
+                let span = Span {
+                    start: offset,
+                    end: offset + self.quote.len_utf8(),
+                    grapheme_column,
+                };
+                if self.strict_syntax {
+                    return Err(EsvError::BareQuote {
+                        line: line_num,
+                        column,
+                        span,
+                    });
                 }
-                Some('\n') => {
-                    chars.next();
-                    return Ok((field, FieldTerminator::LineBreak));
+                if let Some(warnings) = warnings {
+                    warnings.push(EsvWarning::BareQuote {
+                        line: line_num,
+                        column,
+                        span,
+                    });
                 }
-                Some(&c) => {
+            }
+        }
+
+        match terminator {
+            FieldTerminator::Separator => cursor.advance(self.separator.len()),
+            FieldTerminator::LineBreak => {
+                cursor.skip_line();
+            }
+            FieldTerminator::Eof => {}
+        }
+        Ok((Cow::Borrowed(field), terminator))
+    }
+
+    /// Unescape the raw text of a quoted field: escaped quotes (and with
+    /// `EscapeStyle::Backslash`, `\\`, `\n` and `\r`) are replaced, and CR or CRLF line
+    /// breaks become LF unless line endings are preserved
+    fn unescape_quoted(&self, raw: &str) -> String {
+        let mut field = String::with_capacity(raw.len());
+        let mut chars = raw.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                // Quotes only appear escaped inside a quoted field
+                c if c == self.quote && self.escape_style == EscapeStyle::Doubled => {
                     chars.next();
-                    *column += 1;
                     field.push(c);
                 }
-                None => {
-                    return Ok((field, FieldTerminator::Eof));
+                '\\' if self.escape_style == EscapeStyle::Backslash => {
+                    let escaped = |&c: &char| c == self.quote || matches!(c, '\\' | 'n' | 'r');
+                    match chars.next_if(escaped) {
+                        Some('n') => field.push('\n'),
+                        Some('r') => field.push('\r'),
+                        Some(c) => field.push(c),
+                        None => field.push('\\'),
+                    }
+                }
+                '\r' if !self.preserve_line_endings => {
+                    chars.next_if_eq(&'\n');
+                    field.push('\n');
                 }
+                c => field.push(c),
             }
         }
+        field
     }
-}
 
-/// What terminated a field
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum FieldTerminator {
-    Separator,
-    LineBreak,
-    Eof,
+    /// Whether the separator appears next in the input as a standalone grapheme
+    /// cluster (so 🔥 inside the ZWJ sequence ❤️‍🔥 is not a field boundary)
+    fn at_separator(&self, cursor: &Cursor<'_>) -> bool {
+        cursor.rest().starts_with(self.separator.as_str())
+            && cursor.is_grapheme_boundary(cursor.offset())
+            && cursor.is_grapheme_boundary(cursor.offset() + self.separator.len())
+    }
+
+    /// Consume the separator if `at_separator()`
+    fn eat_separator(&self, cursor: &mut Cursor<'_>) -> bool {
+        let found = self.at_separator(cursor);
+        if found {
+            cursor.advance(self.separator.len());
+        }
+        found
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Line breaks ending the records parsed so far, for `EsvWarning::MixedLineEndings`
+#[derive(Debug, Default)]
+struct LineEndings<'a> {
+    first: Option<&'a str>,
+    mixed: bool,
+}
 
-    #[test]
-    fn test_parse_simple_record() {
-        let parser = EsvParser::new();
-        let doc = parser.parse("aaa🔥bbb🔥ccc").unwrap();
-        assert_eq!(doc.records, vec![vec!["aaa", "bbb", "ccc"]]);
+/// Warn about a dropped trailing empty record, or the first record whose line break
+/// differs from the first record's
+fn record_end_warnings<'a>(
+    cursor: &Cursor<'a>,
+    position: &EsvPosition,
+    dropped: bool,
+    line_endings: &mut LineEndings<'a>,
+    warnings: &mut Vec<EsvWarning>,
+) {
+    if dropped {
+        // A record starting at the end of the input is just the final line break
+        if position.len > 0 {
+            warnings.push(EsvWarning::TrailingEmptyRecord {
+                line: position.line,
+                span: position.span(),
+            });
+        }
+        return;
+    }
+
+    let end = position.offset + position.len;
+    let line_break = cursor.slice(end, cursor.offset());
+    match line_endings.first {
+        _ if line_break.is_empty() || line_endings.mixed => {}
+        None => line_endings.first = Some(line_break),
+        Some(first) if first != line_break => {
+            line_endings.mixed = true;
+            let (_, grapheme_column) = cursor.columns(end);
+            warnings.push(EsvWarning::MixedLineEndings {
+                line: position.end_line,
+                expected: line_break_name(first),
+                found: line_break_name(line_break),
+                span: Span {
+                    start: end,
+                    end: cursor.offset(),
+                    grapheme_column,
+                },
+            });
+        }
+        Some(_) => {}
+    }
+}
+
+/// Cursor over the input that tracks how many bytes have been consumed
+#[derive(Debug, Clone)]
+pub(crate) struct Cursor<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    /// Byte offset of the next unread character
+    pub(crate) fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// The unread remainder of the input
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    /// The input between two byte offsets
+    fn slice(&self, start: usize, end: usize) -> &'a str {
+        &self.input[start..end]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn is_grapheme_boundary(&self, offset: usize) -> bool {
+        is_grapheme_boundary(self.input, offset)
+    }
+
+    /// Skip to the next occurrence of any of `bytes` (or the end of the input), for
+    /// when `skip_until()` is not enough
+    ///
+    /// The bytes must be ASCII or UTF-8 lead bytes so the cursor stays on a char
+    /// boundary.
+    fn skip_until_any(&mut self, bytes: &[u8]) {
+        let rest = &self.input.as_bytes()[self.pos..];
+        self.pos += rest
+            .iter()
+            .position(|b| bytes.contains(b))
+            .unwrap_or(rest.len());
+    }
+
+    /// Skip to the next occurrence of any of three bytes (or the end of the input)
+    ///
+    /// The bytes must be ASCII or UTF-8 lead bytes so the cursor stays on a char
+    /// boundary.
+    fn skip_until(&mut self, a: u8, b: u8, c: u8) {
+        let rest = &self.input.as_bytes()[self.pos..];
+        self.pos += memchr3(a, b, c, rest).unwrap_or(rest.len());
+    }
+
+    /// Character and grapheme columns (1-based) of byte offset `pos` on its line
+    ///
+    /// Only called when reporting errors, so the parsing loops don't have to count.
+    fn columns(&self, pos: usize) -> (usize, usize) {
+        columns(&self.input[line_start(self.input, pos)..pos])
+    }
+
+    /// Position of the record parsed from byte offset `start`, which began on `line`,
+    /// up to the cursor; `line_num` is the line number after parsing it
+    ///
+    /// The line break that ended the record is excluded. The index is left at 0 for
+    /// the caller to fill in.
+    pub(crate) fn record_position(
+        &self,
+        start: usize,
+        line: usize,
+        line_num: usize,
+    ) -> EsvPosition {
+        let record = self.slice(start, self.pos);
+        let text = record
+            .strip_suffix("\r\n")
+            .or_else(|| record.strip_suffix(['\r', '\n']));
+        EsvPosition {
+            index: 0,
+            line,
+            end_line: if text.is_some() {
+                line_num - 1
+            } else {
+                line_num
+            },
+            offset: start,
+            len: text.unwrap_or(record).len(),
+        }
+    }
+
+    /// Move to byte offset `pos` (must be a char boundary)
+    fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    /// Skip past the next line break (CR, LF or CRLF), returning false if there is none
+    fn skip_line(&mut self) -> bool {
+        let rest = &self.input.as_bytes()[self.pos..];
+        let Some(index) = memchr2(b'\r', b'\n', rest) else {
+            self.pos = self.input.len();
+            return false;
+        };
+        self.pos += index + 1;
+        if rest[index] == b'\r' && self.peek() == Some('\n') {
+            self.pos += 1;
+        }
+        true
+    }
+
+    /// Skip `len` bytes (must land on a char boundary)
+    pub(crate) fn advance(&mut self, len: usize) {
+        self.pos += len;
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+}
+
+/// What terminated a field
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldTerminator {
+    Separator,
+    LineBreak,
+    Eof,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_columns_count_characters() {
+        let parser = EsvParser::new();
+        assert_eq!(
+            parser.parse("日本🔥\"a😀b\"x"),
+            Err(EsvError::UnexpectedCharAfterQuote {
+                line: 1,
+                column: 9,
+                found: 'x',
+                span: Span {
+                    start: 18,
+                    end: 19,
+                    grapheme_column: 9,
+                },
+            })
+        );
+        assert_eq!(
+            parser.parse("ok\n❤️‍🔥🔥é🔥\"never closed"),
+            Err(EsvError::UnclosedQuote {
+                line: 2,
+                column: 8,
+                span: Span {
+                    start: 26,
+                    end: 39,
+                    grapheme_column: 5,
+                },
+            })
+        );
+        assert_eq!(
+            parser.parse("\"a\r\nbé\"x"),
+            Err(EsvError::UnexpectedCharAfterQuote {
+                line: 2,
+                column: 4,
+                found: 'x',
+                span: Span {
+                    start: 8,
+                    end: 9,
+                    grapheme_column: 4,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient_collects_errors() {
+        let input = "a🔥b\n\"bad\"x🔥c\nd🔥e\n\"unclosed🔥f\ng🔥h\n";
+        let report = EsvParser::new().parse_lenient(input).unwrap();
+        assert_eq!(
+            report.document.records,
+            vec![vec!["a", "b"], vec!["d", "e"], vec!["g", "h"]]
+        );
+        assert_eq!(
+            report.errors,
+            vec![
+                EsvError::UnexpectedCharAfterQuote {
+                    line: 2,
+                    column: 6,
+                    found: 'x',
+                    span: Span {
+                        start: 12,
+                        end: 13,
+                        grapheme_column: 6,
+                    },
+                },
+                EsvError::UnclosedQuote {
+                    line: 4,
+                    column: 1,
+                    span: Span {
+                        start: 26,
+                        end: 48,
+                        grapheme_column: 1,
+                    },
+                },
+            ]
+        );
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_parse_lenient_malformed_header_row() {
+        let input = "\"h\"x🔥b\n1🔥2\n3🔥4\n";
+        let report = EsvParser::new()
+            .with_headers(true)
+            .parse_lenient(input)
+            .unwrap();
+        assert_eq!(report.document.headers, None);
+        assert_eq!(
+            report.document.records,
+            vec![vec!["1", "2"], vec!["3", "4"]]
+        );
+        assert!(matches!(
+            report.errors[..],
+            [EsvError::UnexpectedCharAfterQuote { line: 1, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_parse_lenient_valid_input() {
+        let input = "name🔥age\n\"Al\nice\"🔥30\r\n";
+        let parser = EsvParser::new().with_headers(true);
+        let report = parser.parse_lenient(input).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.document, parser.parse(input).unwrap());
+    }
+
+    #[test]
+    fn test_parse_lenient_strict_field_count() {
+        let input = "a🔥b\nc\nd🔥e\nf🔥g🔥h";
+        let report = EsvParser::new()
+            .with_strict_field_count(true)
+            .parse_lenient(input)
+            .unwrap();
+        assert_eq!(
+            report.document.records,
+            vec![vec!["a", "b"], vec!["d", "e"]]
+        );
+        assert_eq!(report.errors.len(), 2);
+        assert!(matches!(
+            report.errors[1],
+            EsvError::InconsistentFieldCount {
+                expected: 2,
+                found: 3,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_lenient_fatal_errors() {
+        let parser = EsvParser::new().with_separator(',');
+        assert!(matches!(
+            parser.parse_lenient("a,b"),
+            Err(EsvError::InvalidSeparator { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let doc = EsvParser::new()
+            .parse_bytes("a🔥b\nc🔥d".as_bytes())
+            .unwrap();
+        assert_eq!(doc.records, vec![vec!["a", "b"], vec!["c", "d"]]);
+    }
+
+    #[test]
+    fn test_parse_bytes_invalid_utf8() {
+        let input = b"ok\r\nab\xf0\x9f\x94\xa5c\xff\xfed\n";
+        assert_eq!(
+            EsvParser::new().parse_bytes(input),
+            Err(EsvError::InvalidUtf8 {
+                line: 2,
+                column: 5,
+                offset: 11,
+                span: Span {
+                    start: 11,
+                    end: 12,
+                    grapheme_column: 5,
+                },
+            })
+        );
+
+        // Truncated multi-byte sequence at the end of the input
+        assert_eq!(
+            EsvParser::new().parse_bytes(b"a\xf0\x9f"),
+            Err(EsvError::InvalidUtf8 {
+                line: 1,
+                column: 2,
+                offset: 1,
+                span: Span {
+                    start: 1,
+                    end: 3,
+                    grapheme_column: 2,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_bytes_lossy() {
+        let doc = EsvParser::new()
+            .with_lossy_utf8(true)
+            .parse_bytes(b"a\xff\xfe\xf0\x9f\x94\xa5b")
+            .unwrap();
+        assert_eq!(doc.records, vec![vec!["a\u{FFFD}\u{FFFD}", "b"]]);
+    }
+
+    #[test]
+    fn test_parse_borrowed_avoids_copies() {
+        let input = "plain🔥\"quoted🔥field\"🔥\"say \"\"hi\"\"\"\n\"a\r\nb\"🔥\"c\nd\"";
+        let doc = EsvParser::new().parse_borrowed(input).unwrap();
+        assert_eq!(doc.len(), 2);
+
+        let record = &doc.records[0];
+        assert!(matches!(record[0], Cow::Borrowed("plain")));
+        assert!(matches!(record[1], Cow::Borrowed("quoted🔥field")));
+        assert!(matches!(&record[2], Cow::Owned(s) if s == "say \"hi\""));
+
+        let record = &doc.records[1];
+        assert!(matches!(&record[0], Cow::Owned(s) if s == "a\nb"));
+        assert!(matches!(record[1], Cow::Borrowed("c\nd")));
+    }
+
+    #[test]
+    fn test_parse_borrowed_matches_parse() {
+        let inputs = [
+            "",
+            "a🔥b\r\nc🔥d\r\n",
+            "#!esv sep=🌟 header=true\nx🌟y\n1🌟\"2\"\"\"\n",
+            "\"a\rb\"🔥\"\"\n🔥🔥\n",
+            "❤️‍🔥🔥x",
+        ];
+        for input in inputs {
+            let borrowed = EsvParser::new().parse_borrowed(input).unwrap();
+            assert_eq!(
+                borrowed.into_owned(),
+                EsvParser::new().parse(input).unwrap(),
+                "input: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_simple_record() {
+        let parser = EsvParser::new();
+        let doc = parser.parse("aaa🔥bbb🔥ccc").unwrap();
+        assert_eq!(doc.records, vec![vec!["aaa", "bbb", "ccc"]]);
     }
 
     #[test]
@@ -335,6 +1431,35 @@ mod tests {
         assert_eq!(doc.records, vec![vec!["a\nb", "ccc"]]);
     }
 
+    #[test]
+    fn test_parse_preserve_line_endings() {
+        let input = "\"a\r\nb\rc\"\"\nd\"🔥e\r\nf🔥\"x\r\ny\"\r\n";
+        let doc = EsvParser::new().parse(input).unwrap();
+        assert_eq!(
+            doc.records,
+            vec![vec!["a\nb\nc\"\nd", "e"], vec!["f", "x\ny"]]
+        );
+
+        let parser = EsvParser::new().with_preserve_line_endings(true);
+        let doc = parser.parse_borrowed(input).unwrap();
+        assert_eq!(
+            doc.records,
+            vec![vec!["a\r\nb\rc\"\nd", "e"], vec!["f", "x\r\ny"]]
+        );
+        // Only the `""` escape forces a copy
+        assert!(matches!(doc.records[0][0], Cow::Owned(_)));
+        assert!(matches!(doc.records[1][1], Cow::Borrowed(_)));
+
+        // Line numbers still count every line break
+        let (_, warnings) = parser
+            .parse_with_warnings("\"a\r\nb\"🔥\"c\rd\"\nx\"\n")
+            .unwrap();
+        assert!(matches!(
+            warnings[..],
+            [EsvWarning::BareQuote { line: 4, .. }]
+        ));
+    }
+
     #[test]
     fn test_parse_escaped_quotes() {
         let parser = EsvParser::new();
@@ -360,6 +1485,262 @@ mod tests {
         assert_eq!(doc.records, vec![vec!["Alice", "30"]]);
     }
 
+    #[test]
+    fn test_parse_header_policy() {
+        let input = "id🔥 name🔥id\n1🔥Alice🔥x\n";
+        let doc = EsvParser::new().with_headers(true).parse(input).unwrap();
+        assert_eq!(
+            doc.headers,
+            Some(vec![
+                "id".to_string(),
+                " name".to_string(),
+                "id".to_string()
+            ])
+        );
+
+        let parser = EsvParser::new()
+            .with_headers(true)
+            .with_header_policy(HeaderPolicy::Reject);
+        assert_eq!(
+            parser.parse(input),
+            Err(EsvError::HeaderWhitespace {
+                name: " name".to_string(),
+                field: 2,
+                line: 1,
+                span: Span {
+                    start: 6,
+                    end: 11,
+                    grapheme_column: 4,
+                },
+            })
+        );
+        let report = parser.parse_lenient(input).unwrap();
+        assert!(matches!(
+            report.errors.as_slice(),
+            [
+                EsvError::HeaderWhitespace { field: 2, .. },
+                EsvError::DuplicateHeader {
+                    field: 3,
+                    first_field: 1,
+                    span: Span {
+                        start: 15,
+                        end: 17,
+                        ..
+                    },
+                    ..
+                }
+            ]
+        ));
+        assert!(report.errors[1]
+            .render(input)
+            .ends_with("1 | id🔥 name🔥id\n  |            ^^"));
+
+        // Quoted header fields are underlined with their quotes
+        let err = parser.parse("\"a\"🔥\"\"\n").unwrap_err();
+        assert_eq!(
+            err.span(),
+            Some(Span {
+                start: 7,
+                end: 9,
+                grapheme_column: 5
+            })
+        );
+        // Only the header row is checked
+        assert!(parser.parse("a🔥b\n🔥a\n").is_ok());
+        assert!(EsvParser::new()
+            .with_header_policy(HeaderPolicy::Reject)
+            .parse(input)
+            .is_ok());
+
+        // Renamed headers are what strict field count errors name
+        let parser = EsvParser::new()
+            .with_headers(true)
+            .with_header_policy(HeaderPolicy::Rename)
+            .with_strict_field_count(true);
+        let doc = parser.parse(input.trim_end()).unwrap();
+        assert_eq!(
+            doc.headers,
+            Some(vec![
+                "id".to_string(),
+                "name".to_string(),
+                "id_2".to_string()
+            ])
+        );
+        assert_eq!(
+            parser.parse("a🔥🔥a\n1").unwrap_err().to_string(),
+            "record at line 2 has no fields for headers 'column_2', 'a_2'"
+        );
+    }
+
+    #[test]
+    fn test_parse_ragged_policy() {
+        let input = "id🔥name🔥city\n1🔥Alice\n2🔥Bob🔥Oslo🔥x\n";
+        let parse = |policy: RaggedPolicy| {
+            EsvParser::new()
+                .with_headers(true)
+                .with_ragged_policy(policy)
+                .parse(input)
+        };
+
+        let doc = parse(RaggedPolicy::Keep).unwrap();
+        assert_eq!(
+            doc.records,
+            vec![vec!["1", "Alice"], vec!["2", "Bob", "Oslo", "x"]]
+        );
+
+        let doc = parse(RaggedPolicy::pad_empty()).unwrap();
+        assert_eq!(
+            doc.records,
+            vec![vec!["1", "Alice", ""], vec!["2", "Bob", "Oslo", "x"]]
+        );
+        let doc = parse(RaggedPolicy::Pad("n/a".to_string())).unwrap();
+        assert_eq!(doc.records[0], vec!["1", "Alice", "n/a"]);
+
+        let doc = parse(RaggedPolicy::Truncate).unwrap();
+        assert_eq!(
+            doc.records,
+            vec![vec!["1", "Alice"], vec!["2", "Bob", "Oslo"]]
+        );
+
+        assert_eq!(
+            parse(RaggedPolicy::Error).unwrap_err().to_string(),
+            "record at line 2 has no field for header 'city'"
+        );
+
+        // The empty record after a final newline is not a ragged record
+        let doc = EsvParser::new()
+            .with_ragged_policy(RaggedPolicy::Error)
+            .parse("a🔥b\nc🔥d\n")
+            .unwrap();
+        assert_eq!(doc.records, vec![vec!["a", "b"], vec!["c", "d"]]);
+
+        // Without headers the first record sets the width
+        let doc = EsvParser::new()
+            .with_ragged_policy(RaggedPolicy::pad_empty())
+            .parse("a🔥b\nc\n")
+            .unwrap();
+        assert_eq!(doc.records, vec![vec!["a", "b"], vec!["c", ""]]);
+
+        // Lenient parsing reports every ragged record
+        let report = EsvParser::new()
+            .with_ragged_policy(RaggedPolicy::Error)
+            .parse_lenient("a🔥b\nc\nd🔥e\nf🔥g🔥h")
+            .unwrap();
+        assert_eq!(
+            report.document.records,
+            vec![vec!["a", "b"], vec!["d", "e"]]
+        );
+        assert_eq!(report.errors.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_comments_and_blank_lines() {
+        let input = "# units table\nname🔥unit\n\n#m🔥ignored\n\"# not a comment\"🔥x\nlength🔥\"m\n# kept\"\n\n# end";
+        let doc = EsvParser::new().parse(input).unwrap();
+        assert_eq!(doc.records.len(), 8);
+
+        let parser = EsvParser::new()
+            .with_headers(true)
+            .with_comment_prefix("#")
+            .with_skip_blank_lines(true)
+            .with_strict_field_count(true)
+            .with_positions(true);
+        let doc = parser.parse(input).unwrap();
+        assert_eq!(
+            doc.headers,
+            Some(vec!["name".to_string(), "unit".to_string()])
+        );
+        assert_eq!(
+            doc.records,
+            vec![vec!["# not a comment", "x"], vec!["length", "m\n# kept"]]
+        );
+        let lines: Vec<_> = doc.positions.unwrap().iter().map(|p| p.line).collect();
+        assert_eq!(lines, [5, 6]);
+
+        // Blank lines are records unless skipped
+        let parser = EsvParser::new().with_comment_prefix("//");
+        let doc = parser.parse("// a\n\nb\n// c\n").unwrap();
+        assert_eq!(doc.records, vec![vec![""], vec!["b"]]);
+        let doc = parser
+            .with_skip_blank_lines(true)
+            .parse("\r\n\n\rb")
+            .unwrap();
+        assert_eq!(doc.records, vec![vec!["b"]]);
+
+        // Errors point at the right line past skipped lines
+        let err = EsvParser::new()
+            .with_comment_prefix("#")
+            .parse("#1\n#2\n\"a\"b")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            EsvError::UnexpectedCharAfterQuote { line: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_quote_and_escape_style() {
+        let parser = EsvParser::new().with_quote('\'');
+        let doc = parser.parse("'it''s'🔥\"hi\"🔥'a🔥\nb'\nx").unwrap();
+        assert_eq!(
+            doc.records,
+            vec![vec!["it's", "\"hi\"", "a🔥\nb"], vec!["x"]]
+        );
+        let (_, warnings) = parser.parse_with_warnings("ab'c").unwrap();
+        assert!(matches!(
+            warnings.as_slice(),
+            [EsvWarning::BareQuote { column: 3, .. }]
+        ));
+
+        let parser = EsvParser::new().with_escape_style(EscapeStyle::Backslash);
+        let input = "\"a\\\"b\\\\c\\nd\\x\"🔥\"e\\\nf\\r\"\n\"\"🔥\\\"";
+        let doc = parser.parse(input).unwrap();
+        assert_eq!(
+            doc.records,
+            vec![vec!["a\"b\\c\nd\\x", "e\\\nf\r"], vec!["", "\\\""]]
+        );
+        // Doubled quotes close the field
+        assert!(matches!(
+            parser.parse("\"a\"\"b\""),
+            Err(EsvError::UnexpectedCharAfterQuote { found: '"', .. })
+        ));
+        // An escaped closing quote leaves the field open
+        assert!(matches!(
+            parser.parse("\"a\\\"\nb"),
+            Err(EsvError::UnclosedQuote { line: 1, .. })
+        ));
+
+        let parser = EsvParser::new()
+            .with_separator('|')
+            .with_separator_policy(SeparatorPolicy::allowlist(["|"]));
+        assert_eq!(
+            parser.clone().with_quote('|').parse("a"),
+            Err(EsvError::InvalidQuote {
+                quote: '|',
+                reason: "the separator contains it".to_string(),
+            })
+        );
+        assert!(matches!(
+            parser.with_quote('x').parse_lenient("a"),
+            Err(EsvError::InvalidQuote { quote: 'x', .. })
+        ));
+
+        // A quoted field would read as a comment
+        let parser = EsvParser::new().with_quote('#').with_comment_prefix("#");
+        assert_eq!(
+            parser.parse("#a🔥b#\n"),
+            Err(EsvError::InvalidQuote {
+                quote: '#',
+                reason: "comment lines start with it".to_string(),
+            })
+        );
+        let mut reader = crate::EsvReader::from_parser(parser, "#a🔥b#\n".as_bytes());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(EsvError::InvalidQuote { quote: '#', .. }))
+        ));
+    }
+
     #[test]
     fn test_parse_custom_separator() {
         let parser = EsvParser::new().with_separator('😀');
@@ -367,6 +1748,281 @@ mod tests {
         assert_eq!(doc.records, vec![vec!["aaa", "bbb", "ccc"]]);
     }
 
+    #[test]
+    fn test_parse_multi_codepoint_separators() {
+        for separator in ["🏳️‍🌈", "🇺🇸", "👍🏽", "❤️"] {
+            let parser = EsvParser::new().with_separator(separator);
+            let input = format!("aaa{separator}\"b{separator}b\"{separator}ccc\nddd");
+            let doc = parser.parse(&input).unwrap();
+            assert_eq!(
+                doc.records,
+                vec![
+                    vec![
+                        "aaa".to_string(),
+                        format!("b{separator}b"),
+                        "ccc".to_string()
+                    ],
+                    vec!["ddd".to_string()],
+                ],
+                "separator: {separator}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_separator_matched_atomically() {
+        // Only the full skin-tone sequence separates; the bare base emoji is data
+        let parser = EsvParser::new().with_separator("👍🏽");
+        let doc = parser.parse("a👍b👍🏽c").unwrap();
+        assert_eq!(doc.records, vec![vec!["a👍b", "c"]]);
+    }
+
+    #[test]
+    fn test_parse_zwj_sequence_containing_separator() {
+        let parser = EsvParser::new();
+        let doc = parser.parse("❤️‍🔥🔥b\nx🔥❤️‍🔥").unwrap();
+        assert_eq!(doc.records, vec![vec!["❤️‍🔥", "b"], vec!["x", "❤️‍🔥"]]);
+    }
+
+    #[test]
+    fn test_parse_separator_fused_with_modifier_is_data() {
+        // 🔥 followed by a combining mark forms a single grapheme cluster
+        let parser = EsvParser::new();
+        let doc = parser.parse("a🔥\u{301}b🔥c").unwrap();
+        assert_eq!(doc.records, vec![vec!["a🔥\u{301}b", "c"]]);
+    }
+
+    #[test]
+    fn test_parse_flag_separator_respects_regional_indicator_pairs() {
+        // 🇯🇺🇸 pairs as 🇯🇺 + 🇸, so the 🇺🇸 inside it is not a separator
+        let parser = EsvParser::new().with_separator("🇺🇸");
+        let doc = parser.parse("a🇯🇺🇸b🇺🇸c").unwrap();
+        assert_eq!(doc.records, vec![vec!["a🇯🇺🇸b", "c"]]);
+    }
+
+    #[test]
+    fn test_parse_separator_policy() {
+        let parser = EsvParser::new()
+            .with_separator('§')
+            .with_separator_policy(SeparatorPolicy::allowlist(["⭐", "§"]));
+        let doc = parser.parse("a§b").unwrap();
+        assert_eq!(doc.records, vec![vec!["a", "b"]]);
+
+        let parser = EsvParser::new()
+            .with_separator('➖')
+            .with_separator_policy(SeparatorPolicy::denylist(["➖"]));
+        assert!(matches!(
+            parser.parse("a➖b"),
+            Err(EsvError::InvalidSeparator {
+                reason: crate::SeparatorRejection::Denylisted,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_pragma_configures_parser() {
+        let parser = EsvParser::new();
+        let doc = parser
+            .parse("#!esv sep=🌟 header=true eol=crlf\r\nname🌟age\r\nAlice🌟30\r\n")
+            .unwrap();
+        assert_eq!(
+            doc.headers,
+            Some(vec!["name".to_string(), "age".to_string()])
+        );
+        assert_eq!(doc.records, vec![vec!["Alice", "30"]]);
+    }
+
+    #[test]
+    fn test_parse_pragma_separator_still_validated() {
+        let result = EsvParser::new().parse("#!esv sep=,\na,b");
+        assert!(matches!(result, Err(EsvError::InvalidSeparator { .. })));
+    }
+
+    #[test]
+    fn test_parse_pragma_modes() {
+        let input = "#!esv header=true\na🔥b\n";
+
+        let doc = EsvParser::new()
+            .with_pragma_mode(PragmaMode::Ignore)
+            .parse(input)
+            .unwrap();
+        assert_eq!(doc.records, vec![vec!["#!esv header=true"], vec!["a", "b"]]);
+
+        let parser = EsvParser::new().with_pragma_mode(PragmaMode::Require);
+        assert!(parser.parse(input).unwrap().headers.is_some());
+        assert_eq!(parser.parse("a🔥b\n"), Err(EsvError::MissingPragma));
+        assert_eq!(parser.parse(""), Err(EsvError::MissingPragma));
+    }
+
+    #[test]
+    fn test_parse_pragma_line_numbers() {
+        let result = EsvParser::new().parse("#!esv\nok\n\"unclosed");
+        assert_eq!(
+            result,
+            Err(EsvError::UnclosedQuote {
+                line: 3,
+                column: 1,
+                span: Span {
+                    start: 9,
+                    end: 18,
+                    grapheme_column: 1,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_line_numbers_count_quoted_line_breaks() {
+        let parser = EsvParser::new();
+        let result = parser.parse("a🔥\"x\ny\"\n\"p\r\nq\rr\"🔥s\n\"t\"u");
+        assert!(matches!(
+            result,
+            Err(EsvError::UnexpectedCharAfterQuote {
+                line: 6,
+                column: 4,
+                ..
+            })
+        ));
+
+        // Unclosed quotes point at the line of the opening quote
+        assert_eq!(
+            parser.parse("\"a\nb\"🔥\"open"),
+            Err(EsvError::UnclosedQuote {
+                line: 2,
+                column: 4,
+                span: Span {
+                    start: 9,
+                    end: 14,
+                    grapheme_column: 4,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_inconsistent_field_count_lines() {
+        let parser = EsvParser::new().with_strict_field_count(true);
+        let err = parser.parse("a🔥b\n\"x\ny\nz\"\n").unwrap_err();
+        assert_eq!(
+            err,
+            EsvError::InconsistentFieldCount {
+                expected: 2,
+                found: 1,
+                line: 2,
+                end_line: 4,
+                span: Span {
+                    start: 7,
+                    end: 14,
+                    grapheme_column: 1,
+                },
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "inconsistent field count at lines 2-4: expected 2 fields, found 1"
+        );
+
+        let result = parser.parse("h1🔥h2\n\"multi\nline\"🔥b\nc");
+        assert!(matches!(
+            result,
+            Err(EsvError::InconsistentFieldCount {
+                line: 4,
+                end_line: 4,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_positions() {
+        let input = "id🔥note\n1🔥\"two\nlines\"\n2🔥x\r\n3🔥\"\"\n";
+        let parser = EsvParser::new().with_headers(true).with_positions(true);
+        let doc = parser.parse(input).unwrap();
+        let positions = doc.positions.clone().unwrap();
+        assert_eq!(
+            positions,
+            vec![
+                EsvPosition {
+                    index: 0,
+                    line: 2,
+                    end_line: 3,
+                    offset: 11,
+                    len: 16,
+                },
+                EsvPosition {
+                    index: 1,
+                    line: 4,
+                    end_line: 4,
+                    offset: 28,
+                    len: 6,
+                },
+                EsvPosition {
+                    index: 2,
+                    line: 5,
+                    end_line: 5,
+                    offset: 36,
+                    len: 7,
+                },
+            ]
+        );
+        assert_eq!(
+            parser.parse_borrowed(input).unwrap().positions,
+            doc.positions
+        );
+
+        // Each position slices out exactly its record
+        for (position, record) in positions.iter().zip(&doc.records) {
+            let text = &input[position.offset..position.offset + position.len];
+            assert_eq!(&EsvParser::new().parse(text).unwrap().records[0], record);
+        }
+
+        // Positions are only tracked on request
+        let doc = EsvParser::new().with_headers(true).parse(input).unwrap();
+        assert_eq!(doc.positions, None);
+
+        // Empty input still reports (no) positions when they are requested
+        for input in ["", "#!esv header=true\n"] {
+            assert_eq!(parser.parse(input).unwrap().positions, Some(vec![]));
+        }
+        assert_eq!(EsvParser::new().parse("").unwrap().positions, None);
+    }
+
+    #[test]
+    fn test_parse_lenient_positions() {
+        let report = EsvParser::new()
+            .with_positions(true)
+            .parse_lenient("a\n\"b\nc\"x\n\"d\ne")
+            .unwrap();
+        assert_eq!(report.document.records, vec![vec!["a"], vec!["e"]]);
+        assert_eq!(
+            report.document.positions,
+            Some(vec![
+                EsvPosition {
+                    index: 0,
+                    line: 1,
+                    end_line: 1,
+                    offset: 0,
+                    len: 1,
+                },
+                EsvPosition {
+                    index: 1,
+                    line: 5,
+                    end_line: 5,
+                    offset: 12,
+                    len: 1,
+                },
+            ])
+        );
+        assert!(matches!(
+            report.errors[..],
+            [
+                EsvError::UnexpectedCharAfterQuote { line: 3, .. },
+                EsvError::UnclosedQuote { line: 4, .. },
+            ]
+        ));
+    }
+
     #[test]
     fn test_parse_crlf_line_endings() {
         let parser = EsvParser::new();
@@ -393,6 +2049,120 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_bare_quotes() {
+        let input = "a🔥b\n5\"🔥ok\n";
+        // Kept as a literal character by default
+        let doc = EsvParser::new().parse(input).unwrap();
+        assert_eq!(doc.records, vec![vec!["a", "b"], vec!["5\"", "ok"]]);
+
+        let result = EsvParser::new().with_strict_syntax(true).parse(input);
+        assert_eq!(
+            result,
+            Err(EsvError::BareQuote {
+                line: 2,
+                column: 2,
+                span: Span {
+                    start: 8,
+                    end: 9,
+                    grapheme_column: 2,
+                },
+            })
+        );
+
+        // Quoted fields and quotes after the separator are unaffected
+        let parser = EsvParser::new().with_strict_syntax(true);
+        assert!(parser.parse("\"a\"\"b\"🔥c\n").is_ok());
+        assert!(matches!(
+            parser.parse("a🔥b\"c"),
+            Err(EsvError::BareQuote { column: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_with_warnings() {
+        let (doc, warnings) = EsvParser::new()
+            .parse_with_warnings("a🔥b\nc🔥d\n")
+            .unwrap();
+        assert_eq!(doc.records.len(), 2);
+        assert!(warnings.is_empty());
+
+        let input = "a🔥\"x\r\ny\r\nz\"\nb\"🔥c\r\n\"\"";
+        let (doc, warnings) = EsvParser::new().parse_with_warnings(input).unwrap();
+        assert_eq!(doc.records, vec![vec!["a", "x\ny\nz"], vec!["b\"", "c"]]);
+        assert_eq!(
+            warnings,
+            vec![
+                EsvWarning::NormalizedLineBreak {
+                    line: 1,
+                    column: 5,
+                    span: Span {
+                        start: 7,
+                        end: 9,
+                        grapheme_column: 5,
+                    },
+                },
+                EsvWarning::BareQuote {
+                    line: 4,
+                    column: 2,
+                    span: Span {
+                        start: 16,
+                        end: 17,
+                        grapheme_column: 2,
+                    },
+                },
+                EsvWarning::MixedLineEndings {
+                    line: 4,
+                    expected: "LF",
+                    found: "CRLF",
+                    span: Span {
+                        start: 22,
+                        end: 24,
+                        grapheme_column: 5,
+                    },
+                },
+                EsvWarning::TrailingEmptyRecord {
+                    line: 5,
+                    span: Span {
+                        start: 24,
+                        end: 26,
+                        grapheme_column: 1,
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient_bare_quotes() {
+        let input = "a🔥b\"\nc🔥d\ne\"🔥f\n";
+        let report = EsvParser::new().parse_lenient(input).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.document.records.len(), 3);
+        assert!(matches!(
+            report.warnings.as_slice(),
+            [
+                EsvWarning::BareQuote { line: 1, .. },
+                EsvWarning::BareQuote { line: 3, .. }
+            ]
+        ));
+
+        // Strict syntax turns them into errors and skips the records
+        let report = EsvParser::new()
+            .with_strict_syntax(true)
+            .parse_lenient(input)
+            .unwrap();
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.document.records, vec![vec!["c", "d"]]);
+        assert!(matches!(
+            report.errors.as_slice(),
+            [
+                EsvError::BareQuote { line: 1, .. },
+                EsvError::BareQuote { line: 3, .. }
+            ]
+        ));
+    }
+
     #[test]
     fn test_parse_strict_field_count() {
         let parser = EsvParser::new().with_strict_field_count(true);
@@ -403,6 +2173,77 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_parse_strict_field_count_names_headers() {
+        let parser = EsvParser::new()
+            .with_strict_field_count(true)
+            .with_headers(true);
+
+        let err = parser.parse("name🔥age🔥city\nAlice🔥30\n").unwrap_err();
+        assert_eq!(
+            err,
+            EsvError::HeaderMismatch {
+                missing: vec!["city".to_string()],
+                extra: 0,
+                line: 2,
+                end_line: 2,
+                span: Span {
+                    start: 20,
+                    end: 31,
+                    grapheme_column: 1,
+                },
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "record at line 2 has no field for header 'city'"
+        );
+
+        // A final newline does not leave a record without fields
+        let doc = parser.parse("name🔥age\nAlice🔥30\n").unwrap();
+        assert_eq!(doc.records, vec![vec!["Alice", "30"]]);
+
+        let err = parser.parse("name🔥age🔥city\nAlice\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "record at line 2 has no fields for headers 'age', 'city'"
+        );
+
+        let err = parser
+            .parse("name🔥age\nAlice🔥30🔥x🔥\"y\nz\"\n")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "record at lines 2-3 has 2 fields past the last header"
+        );
+
+        // Lenient parsing reports every record against the header row
+        let report = parser.parse_lenient("a🔥b\n1\n2🔥3\n4🔥5🔥6").unwrap();
+        assert_eq!(report.document.records, vec![vec!["2", "3"]]);
+        assert!(matches!(
+            report.errors.as_slice(),
+            [
+                EsvError::HeaderMismatch {
+                    line: 2,
+                    extra: 0,
+                    ..
+                },
+                EsvError::HeaderMismatch {
+                    line: 4,
+                    extra: 1,
+                    ..
+                }
+            ]
+        ));
+
+        // Without headers the first record is just the expected shape
+        let err = EsvParser::new()
+            .with_strict_field_count(true)
+            .parse("a🔥b\n1\n")
+            .unwrap_err();
+        assert!(matches!(err, EsvError::InconsistentFieldCount { .. }));
+    }
+
     #[test]
     fn test_parse_empty_input() {
         let parser = EsvParser::new();
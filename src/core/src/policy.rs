@@ -0,0 +1,175 @@
+//! Separator policies
+//!
+//! Controls which separators `EsvParser` and `EsvSerializer` accept. The default
+//! policy only allows emoji; allowlists, denylists and a permissive mode cover
+//! legacy files and house rules.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::error::EsvError;
+use crate::error::SeparatorRejection;
+use crate::is_emoji_sequence;
+
+/// Policy deciding which separators are accepted
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum SeparatorPolicy {
+    /// Only emoji and emoji sequences (the default)
+    #[default]
+    StrictEmoji,
+    /// Only the listed separators, whether or not they are emoji
+    Allowlist(Vec<String>),
+    /// Any emoji or emoji sequence except the listed separators
+    Denylist(Vec<String>),
+    /// Any single grapheme cluster made only of non-ASCII characters
+    Permissive,
+}
+
+impl SeparatorPolicy {
+    /// Create an allowlist policy from a list of separators
+    pub fn allowlist<I, S>(separators: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::Allowlist(separators.into_iter().map(Into::into).collect())
+    }
+
+    /// Create a denylist policy from a list of separators
+    pub fn denylist<I, S>(separators: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::Denylist(separators.into_iter().map(Into::into).collect())
+    }
+
+    /// Validate a separator against this policy
+    ///
+    /// Regardless of policy, a separator may never be empty or contain double quotes
+    /// or line breaks, since those would make the format ambiguous.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::InvalidSeparator` describing which rule rejected the separator.
+    pub fn validate(&self, separator: &str) -> Result<(), EsvError> {
+        if separator.is_empty() || separator.contains(['"', '\r', '\n']) {
+            return Err(reject(separator, SeparatorRejection::Reserved));
+        }
+
+        match self {
+            SeparatorPolicy::StrictEmoji => {
+                if !is_emoji_sequence(separator) {
+                    return Err(reject(separator, SeparatorRejection::NotEmoji));
+                }
+            }
+            SeparatorPolicy::Allowlist(allowed) => {
+                if !allowed.iter().any(|s| s == separator) {
+                    return Err(reject(separator, SeparatorRejection::NotAllowlisted));
+                }
+            }
+            SeparatorPolicy::Denylist(denied) => {
+                if denied.iter().any(|s| s == separator) {
+                    return Err(reject(separator, SeparatorRejection::Denylisted));
+                }
+                if !is_emoji_sequence(separator) {
+                    return Err(reject(separator, SeparatorRejection::NotEmoji));
+                }
+            }
+            SeparatorPolicy::Permissive => {
+                if separator.graphemes(true).count() != 1 || separator.chars().any(|c| c.is_ascii())
+                {
+                    return Err(reject(separator, SeparatorRejection::NotPermitted));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn reject(separator: &str, reason: SeparatorRejection) -> EsvError {
+    EsvError::InvalidSeparator {
+        separator: separator.to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(policy: &SeparatorPolicy, separator: &str) -> Option<SeparatorRejection> {
+        match policy.validate(separator) {
+            Ok(()) => None,
+            Err(EsvError::InvalidSeparator { reason, .. }) => Some(reason),
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn test_strict_emoji_policy() {
+        let policy = SeparatorPolicy::StrictEmoji;
+        assert_eq!(reason(&policy, "🔥"), None);
+        assert_eq!(reason(&policy, "🏳️‍🌈"), None);
+        assert_eq!(reason(&policy, "|"), Some(SeparatorRejection::NotEmoji));
+        assert_eq!(reason(&policy, "§"), Some(SeparatorRejection::NotEmoji));
+    }
+
+    #[test]
+    fn test_allowlist_policy() {
+        let policy = SeparatorPolicy::allowlist(["⭐", "✂", "§"]);
+        assert_eq!(reason(&policy, "⭐"), None);
+        assert_eq!(reason(&policy, "✂"), None);
+        // Allowlisted separators need not be emoji
+        assert_eq!(reason(&policy, "§"), None);
+        assert_eq!(
+            reason(&policy, "🔥"),
+            Some(SeparatorRejection::NotAllowlisted)
+        );
+    }
+
+    #[test]
+    fn test_denylist_policy() {
+        let policy = SeparatorPolicy::denylist(["➖", "➕"]);
+        assert_eq!(reason(&policy, "🔥"), None);
+        assert_eq!(reason(&policy, "➖"), Some(SeparatorRejection::Denylisted));
+        assert_eq!(reason(&policy, ","), Some(SeparatorRejection::NotEmoji));
+    }
+
+    #[test]
+    fn test_permissive_policy() {
+        let policy = SeparatorPolicy::Permissive;
+        assert_eq!(reason(&policy, "§"), None);
+        assert_eq!(reason(&policy, "→"), None);
+        assert_eq!(reason(&policy, "🔥"), None);
+        assert_eq!(reason(&policy, ","), Some(SeparatorRejection::NotPermitted));
+        assert_eq!(
+            reason(&policy, "§§"),
+            Some(SeparatorRejection::NotPermitted)
+        );
+        assert_eq!(reason(&policy, "é"), None);
+        assert_eq!(
+            reason(&policy, "e\u{301}"),
+            Some(SeparatorRejection::NotPermitted)
+        );
+    }
+
+    #[test]
+    fn test_reserved_separators_rejected_by_every_policy() {
+        let policies = [
+            SeparatorPolicy::StrictEmoji,
+            SeparatorPolicy::allowlist(["\"", "", "\n"]),
+            SeparatorPolicy::denylist(Vec::<String>::new()),
+            SeparatorPolicy::Permissive,
+        ];
+        for policy in &policies {
+            for separator in ["", "\"", "\n", "\r"] {
+                assert_eq!(
+                    reason(policy, separator),
+                    Some(SeparatorRejection::Reserved),
+                    "policy: {policy:?}, separator: {separator:?}"
+                );
+            }
+        }
+    }
+}
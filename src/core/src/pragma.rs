@@ -0,0 +1,202 @@
+//! Self-describing dialect pragma
+//!
+//! An ESV file may start with a pragma line such as `#!esv sep=🌟 header=true eol=lf`
+//! recording the dialect it was written with, so readers can configure themselves
+//! without out-of-band knowledge.
+
+use std::fmt;
+
+use crate::error::EsvError;
+use crate::LineEnding;
+
+/// Marker that starts a pragma line
+pub const PRAGMA_PREFIX: &str = "#!esv";
+
+/// Dialect settings declared by a pragma line
+///
+/// Every setting is optional; settings absent from the pragma keep the parser's
+/// configured value.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EsvPragma {
+    /// Field separator (`sep=`)
+    pub separator: Option<String>,
+    /// Whether the first record is a header row (`header=true|false`)
+    pub has_headers: Option<bool>,
+    /// Line ending used by the file (`eol=lf|crlf`)
+    pub line_ending: Option<LineEnding>,
+}
+
+/// How the parser treats a pragma line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PragmaMode {
+    /// Apply a pragma if the input starts with one (the default)
+    #[default]
+    Detect,
+    /// Never look for a pragma; a leading `#!esv` line is parsed as data
+    Ignore,
+    /// Fail with `EsvError::MissingPragma` unless the input starts with a pragma
+    Require,
+}
+
+impl EsvPragma {
+    /// Parse a pragma line (without its line break)
+    ///
+    /// Unknown keys are ignored so newer writers stay readable.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::InvalidPragma` if the line does not start with `#!esv`, a
+    /// setting is not in `key=value` form, or a known key has an invalid value.
+    pub fn parse(line: &str) -> Result<Self, EsvError> {
+        let settings = line
+            .strip_prefix(PRAGMA_PREFIX)
+            .filter(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+            .ok_or_else(|| invalid(format!("pragma must start with '{PRAGMA_PREFIX}'")))?;
+
+        let mut pragma = EsvPragma::default();
+        for setting in settings.split_whitespace() {
+            let (key, value) = setting
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected key=value, found '{setting}'")))?;
+            match key {
+                "sep" if !value.is_empty() => pragma.separator = Some(value.to_string()),
+                "sep" => return Err(invalid("separator must not be empty".to_string())),
+                "header" => {
+                    pragma.has_headers = Some(match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => return Err(invalid(format!("invalid header value '{value}'"))),
+                    });
+                }
+                "eol" => {
+                    pragma.line_ending = Some(match value {
+                        "lf" => LineEnding::Lf,
+                        "crlf" => LineEnding::Crlf,
+                        _ => return Err(invalid(format!("invalid eol value '{value}'"))),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(pragma)
+    }
+
+    /// Split a leading pragma line off the input
+    ///
+    /// Returns the pragma line (without its line break) and the remaining input, or
+    /// `None` if the input does not start with a pragma.
+    pub(crate) fn split(input: &str) -> Option<(&str, &str)> {
+        let end = input.find(['\r', '\n']).unwrap_or(input.len());
+        let line = &input[..end];
+        let is_pragma = line
+            .strip_prefix(PRAGMA_PREFIX)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+        if !is_pragma {
+            return None;
+        }
+
+        let rest = &input[end..];
+        let rest = rest
+            .strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+            .or_else(|| rest.strip_prefix('\r'))
+            .unwrap_or(rest);
+        Some((line, rest))
+    }
+}
+
+impl fmt::Display for EsvPragma {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{PRAGMA_PREFIX}")?;
+        if let Some(separator) = &self.separator {
+            write!(f, " sep={separator}")?;
+        }
+        if let Some(has_headers) = self.has_headers {
+            write!(f, " header={has_headers}")?;
+        }
+        if let Some(line_ending) = self.line_ending {
+            let eol = match line_ending {
+                LineEnding::Lf => "lf",
+                LineEnding::Crlf => "crlf",
+            };
+            write!(f, " eol={eol}")?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(reason: String) -> EsvError {
+    EsvError::InvalidPragma { reason }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pragma() {
+        let pragma = EsvPragma::parse("#!esv sep=🌟 header=true eol=crlf").unwrap();
+        assert_eq!(
+            pragma,
+            EsvPragma {
+                separator: Some("🌟".to_string()),
+                has_headers: Some(true),
+                line_ending: Some(LineEnding::Crlf),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_partial_pragma() {
+        let pragma = EsvPragma::parse("#!esv header=false").unwrap();
+        assert_eq!(pragma.separator, None);
+        assert_eq!(pragma.has_headers, Some(false));
+        assert_eq!(EsvPragma::parse("#!esv").unwrap(), EsvPragma::default());
+    }
+
+    #[test]
+    fn test_parse_pragma_ignores_unknown_keys() {
+        let pragma = EsvPragma::parse("#!esv sep=🏳️‍🌈 future=1").unwrap();
+        assert_eq!(pragma.separator.as_deref(), Some("🏳️‍🌈"));
+    }
+
+    #[test]
+    fn test_parse_invalid_pragma() {
+        for line in [
+            "#!esvx sep=🔥",
+            "#!esv header=yes",
+            "#!esv eol=cr",
+            "#!esv sep=",
+            "#!esv header",
+        ] {
+            assert!(
+                matches!(EsvPragma::parse(line), Err(EsvError::InvalidPragma { .. })),
+                "line: {line}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_roundtrip() {
+        let pragma = EsvPragma {
+            separator: Some("👍🏽".to_string()),
+            has_headers: Some(false),
+            line_ending: Some(LineEnding::Lf),
+        };
+        let line = pragma.to_string();
+        assert_eq!(line, "#!esv sep=👍🏽 header=false eol=lf");
+        assert_eq!(EsvPragma::parse(&line).unwrap(), pragma);
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            EsvPragma::split("#!esv sep=🌟\r\na🌟b"),
+            Some(("#!esv sep=🌟", "a🌟b"))
+        );
+        assert_eq!(EsvPragma::split("#!esv"), Some(("#!esv", "")));
+        assert_eq!(EsvPragma::split("#!esvdata\n"), None);
+        assert_eq!(EsvPragma::split("a🔥b\n#!esv\n"), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Quote character and escape style
+//!
+//! RFC 4180 quotes fields with `"` and escapes a quote inside a quoted field by
+//! doubling it. Some producers quote with `'` instead, or escape with a backslash.
+
+use crate::error::EsvError;
+
+/// Default quote character
+pub(crate) const DEFAULT_QUOTE: char = '"';
+
+/// How a quote character inside a quoted field is escaped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EscapeStyle {
+    /// Double the quote (`""`), as in RFC 4180 (the default)
+    #[default]
+    Doubled,
+    /// Precede the quote with a backslash (`\"`); `\\` is a backslash, `\n` a line feed
+    /// and `\r` a carriage return, and any other backslash is kept literally
+    Backslash,
+}
+
+/// Check that `quote` can delimit fields escaped with `escape_style` and split by
+/// `separator`, in input whose comment lines start with `comment_prefix`
+///
+/// The quote must be ASCII punctuation, and can be neither the backslash escape nor
+/// part of the separator. It also cannot start the comment prefix, or a record
+/// starting with a quoted field would read as a comment.
+pub(crate) fn validate_quote(
+    quote: char,
+    escape_style: EscapeStyle,
+    separator: &str,
+    comment_prefix: Option<&str>,
+) -> Result<(), EsvError> {
+    let reason = if !quote.is_ascii_punctuation() {
+        "must be ASCII punctuation"
+    } else if quote == '\\' && escape_style == EscapeStyle::Backslash {
+        "backslash is the escape character"
+    } else if separator.contains(quote) {
+        "the separator contains it"
+    } else if comment_prefix.is_some_and(|prefix| prefix.starts_with(quote)) {
+        "comment lines start with it"
+    } else {
+        return Ok(());
+    };
+    Err(EsvError::InvalidQuote {
+        quote,
+        reason: reason.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_quote() {
+        assert!(validate_quote('"', EscapeStyle::Doubled, "🔥", None).is_ok());
+        assert!(validate_quote('\'', EscapeStyle::Backslash, "🔥", None).is_ok());
+        assert!(validate_quote('\\', EscapeStyle::Doubled, "🔥", None).is_ok());
+
+        let reason = |quote, escape_style, separator| match validate_quote(
+            quote,
+            escape_style,
+            separator,
+            None,
+        ) {
+            Err(EsvError::InvalidQuote { reason, .. }) => reason,
+            result => panic!("expected InvalidQuote, got {result:?}"),
+        };
+        assert_eq!(
+            reason('a', EscapeStyle::Doubled, "🔥"),
+            "must be ASCII punctuation"
+        );
+        assert_eq!(
+            reason('«', EscapeStyle::Doubled, "🔥"),
+            "must be ASCII punctuation"
+        );
+        assert_eq!(
+            reason('\n', EscapeStyle::Doubled, "🔥"),
+            "must be ASCII punctuation"
+        );
+        assert_eq!(
+            reason('\\', EscapeStyle::Backslash, "🔥"),
+            "backslash is the escape character"
+        );
+        assert_eq!(
+            reason('|', EscapeStyle::Doubled, "|"),
+            "the separator contains it"
+        );
+
+        assert!(validate_quote('\'', EscapeStyle::Doubled, "🔥", Some("#")).is_ok());
+        assert!(validate_quote('#', EscapeStyle::Doubled, "🔥", Some("# ")).is_err());
+        assert!(validate_quote('/', EscapeStyle::Doubled, "🔥", Some("//")).is_err());
+
+        assert_eq!(
+            EsvError::InvalidQuote {
+                quote: '|',
+                reason: "the separator contains it".to_string()
+            }
+            .to_string(),
+            "invalid quote character '|': the separator contains it"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Ragged record handling
+//!
+//! Exports often drop trailing empty fields, leaving records shorter than the header
+//! row. A `RaggedPolicy` decides whether such records are kept, evened out or
+//! rejected.
+
+/// How the parser treats records whose field count differs from the first record
+/// (the header row, if any)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RaggedPolicy {
+    /// Keep records as they are (the default)
+    #[default]
+    Keep,
+    /// Pad short records with the filler (often empty) to the width of the first
+    /// record; longer records are kept
+    Pad(String),
+    /// Drop fields past the width of the first record; shorter records are kept
+    Truncate,
+    /// Fail with a field count error, like `EsvParser::with_strict_field_count(true)`
+    Error,
+}
+
+impl RaggedPolicy {
+    /// Pad short records with empty fields
+    #[must_use]
+    pub fn pad_empty() -> Self {
+        Self::Pad(String::new())
+    }
+
+    /// Pad or truncate `record` to `width` fields as the policy says
+    pub(crate) fn fit<S: From<String>>(&self, record: &mut Vec<S>, width: usize) {
+        match self {
+            RaggedPolicy::Pad(filler) if record.len() < width => {
+                record.resize_with(width, || S::from(filler.clone()));
+            }
+            RaggedPolicy::Truncate => record.truncate(width),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fit(policy: &RaggedPolicy, record: &[&str], width: usize) -> Vec<String> {
+        let mut record: Vec<String> = record.iter().map(ToString::to_string).collect();
+        policy.fit(&mut record, width);
+        record
+    }
+
+    #[test]
+    fn test_fit() {
+        let short = ["a"];
+        let long = ["a", "b", "c"];
+        assert_eq!(fit(&RaggedPolicy::Keep, &short, 2), ["a"]);
+        assert_eq!(fit(&RaggedPolicy::pad_empty(), &short, 2), ["a", ""]);
+        assert_eq!(
+            fit(&RaggedPolicy::Pad("-".into()), &short, 3),
+            ["a", "-", "-"]
+        );
+        assert_eq!(
+            fit(&RaggedPolicy::Pad("-".into()), &long, 2),
+            ["a", "b", "c"]
+        );
+        assert_eq!(fit(&RaggedPolicy::Truncate, &long, 2), ["a", "b"]);
+        assert_eq!(fit(&RaggedPolicy::Truncate, &short, 2), ["a"]);
+    }
+}
@@ -0,0 +1,820 @@
+//! Streaming ESV reader
+//!
+//! Reads ESV records one at a time from any `io::BufRead`, so large inputs never
+//! have to be materialized as a single `String` or `EsvDocument`.
+
+use std::io::BufRead;
+
+use crate::error::EsvError;
+use crate::into_owned_record;
+use crate::is_trailing_empty;
+use crate::parser::Cursor;
+use crate::EscapeStyle;
+use crate::EsvParser;
+use crate::EsvPosition;
+use crate::EsvPragma;
+use crate::PragmaMode;
+
+/// Streaming reader that yields ESV records from an `io::BufRead`
+///
+/// Records are parsed with the same quoting and line-ending rules as
+/// `EsvParser::parse`. Input is buffered one physical line at a time; quoted fields
+/// spanning several lines pull in further lines until the record is complete.
+#[derive(Debug)]
+pub struct EsvReader<R> {
+    reader: R,
+    parser: EsvParser,
+    buffer: String,
+    line_num: usize,
+    bytes_read: usize,
+    /// Offset of the start of `buffer` in the (decoded) input
+    buffer_offset: usize,
+    /// Offset in `buffer` from which to look for the end of the quoted field the
+    /// buffered record ran out of input in
+    quote_scan: Option<usize>,
+    headers: Option<Vec<String>>,
+    /// Number of data records returned so far
+    records_read: usize,
+    /// Position of the record most recently returned
+    position: Option<EsvPosition>,
+    expected_field_count: Option<usize>,
+    started: bool,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: BufRead> EsvReader<R> {
+    /// Create a new reader with default parser settings
+    pub fn new(reader: R) -> Self {
+        Self::from_parser(EsvParser::new(), reader)
+    }
+
+    /// Create a new reader using the settings of an existing parser
+    pub fn from_parser(parser: EsvParser, reader: R) -> Self {
+        Self {
+            reader,
+            parser,
+            buffer: String::new(),
+            line_num: 1,
+            bytes_read: 0,
+            buffer_offset: 0,
+            quote_scan: None,
+            headers: None,
+            records_read: 0,
+            position: None,
+            expected_field_count: None,
+            started: false,
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Return the header row, reading it from the input if necessary
+    ///
+    /// Returns `Ok(None)` when the parser is not configured with headers or the
+    /// input is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the separator is invalid or the header row cannot be
+    /// read or parsed.
+    pub fn headers(&mut self) -> Result<Option<&[String]>, EsvError> {
+        self.start()?;
+        Ok(self.headers.as_deref())
+    }
+
+    /// Position of the record most recently returned by the iterator
+    ///
+    /// Returns `None` before the first record and after an error. Offsets count bytes
+    /// from the start of the stream (of the decoded text when lossy decoding replaced
+    /// invalid UTF-8).
+    #[must_use]
+    pub fn position(&self) -> Option<EsvPosition> {
+        self.position
+    }
+
+    /// Consume the reader, returning the underlying `io::BufRead`
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Apply any pragma, validate the separator and read the header row on first use
+    fn start(&mut self) -> Result<(), EsvError> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+
+        // A pragma may change the separator, so apply it before validating
+        if let Err(e) = self.read_pragma().and_then(|()| self.parser.validate()) {
+            self.done = true;
+            return Err(e);
+        }
+
+        if self.parser.has_headers {
+            let mut text = String::new();
+            match self.read_record(Some(&mut text)) {
+                Some(Ok((mut headers, position))) => {
+                    // Without a usable header row there is nothing to key records by
+                    if let Err(e) =
+                        self.parser
+                            .check_header_row(&mut headers, &position, &text, None)
+                    {
+                        self.done = true;
+                        return Err(e);
+                    }
+                    self.headers = Some(headers);
+                }
+                Some(Err(e)) => return Err(e),
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply a pragma on the first line, honoring the parser's `PragmaMode`
+    fn read_pragma(&mut self) -> Result<(), EsvError> {
+        if self.parser.pragma_mode == PragmaMode::Ignore {
+            return Ok(());
+        }
+
+        if self.fill_buffer()? == 0 {
+            self.eof = true;
+        }
+        let split = EsvPragma::split(&self.buffer)
+            .map(|(line, rest)| (EsvPragma::parse(line), self.buffer.len() - rest.len()));
+        match split {
+            Some((pragma, consumed)) => {
+                self.parser.apply_pragma(&pragma?);
+                self.buffer.drain(..consumed);
+                self.buffer_offset += consumed;
+                self.line_num += 1;
+                Ok(())
+            }
+            None if self.parser.pragma_mode == PragmaMode::Require => Err(EsvError::MissingPragma),
+            None => Ok(()),
+        }
+    }
+
+    /// Read the next record and its position, applying the ragged record policy
+    ///
+    /// With `text`, the record's text is copied there.
+    fn read_record(
+        &mut self,
+        text: Option<&mut String>,
+    ) -> Option<Result<(Vec<String>, EsvPosition), EsvError>> {
+        let (mut record, position) = match self.read_raw_record(text)? {
+            Ok(result) => result,
+            Err(e) => return Some(Err(e)),
+        };
+
+        match self.expected_field_count {
+            None => self.expected_field_count = Some(record.len()),
+            Some(expected) if self.parser.rejects_ragged() && record.len() != expected => {
+                let e = position.field_count_error(expected, record.len());
+                return Some(Err(match &self.headers {
+                    Some(headers) => e.name_headers(headers),
+                    None => e,
+                }));
+            }
+            Some(width) => self.parser.ragged_policy.fit(&mut record, width),
+        }
+
+        Some(Ok((record, position)))
+    }
+
+    /// Read and parse the next record and its position without any validation
+    fn read_raw_record(
+        &mut self,
+        mut text: Option<&mut String>,
+    ) -> Option<Result<(Vec<String>, EsvPosition), EsvError>> {
+        let mut need_more = self.buffer.is_empty();
+
+        loop {
+            if need_more && !self.eof {
+                match self.fill_buffer() {
+                    Ok(0) => self.eof = true,
+                    Ok(_) => {
+                        // Only re-parse the record once its open quoted field can end
+                        if let Some(from) = self.quote_scan {
+                            if !self.closes_quoted_field(&self.buffer[from..]) {
+                                self.quote_scan = Some(self.buffer.len());
+                                continue;
+                            }
+                            self.quote_scan = None;
+                        }
+                    }
+                    Err(e) => {
+                        // Unlike invalid UTF-8, an I/O failure leaves nothing to resume from
+                        self.done = matches!(e, EsvError::Io { .. });
+                        return Some(Err(e));
+                    }
+                }
+            }
+
+            if self.buffer.is_empty() {
+                return None;
+            }
+
+            let mut cursor = Cursor::new(&self.buffer);
+            let mut line_num = self.line_num;
+            // The buffer holds whole lines, so comments are recognized before parsing
+            if self.parser.skip_ignored_lines(&mut cursor, &mut line_num) {
+                let consumed = cursor.offset();
+                self.buffer.drain(..consumed);
+                self.buffer_offset += consumed;
+                self.line_num = line_num;
+                need_more = self.buffer.is_empty();
+                continue;
+            }
+            match self.parser.parse_record(&mut cursor, &mut line_num, None) {
+                // The record ran into the end of the buffer; it may continue on the next line
+                Ok((_, true)) if !self.eof => need_more = true,
+                Err(EsvError::UnclosedQuote { .. }) if !self.eof => {
+                    self.quote_scan = Some(self.buffer.len());
+                    need_more = true;
+                }
+                Ok((record, ended_at_eof)) => {
+                    if is_trailing_empty(&record, ended_at_eof) {
+                        self.buffer_offset += self.buffer.len();
+                        self.buffer.clear();
+                        return None;
+                    }
+                    let record = into_owned_record(record);
+                    let mut position = cursor.record_position(0, self.line_num, line_num);
+                    if let Some(text) = text.as_mut() {
+                        text.push_str(&self.buffer[..position.len]);
+                    }
+                    position.offset += self.buffer_offset;
+                    let consumed = cursor.offset();
+                    self.buffer.drain(..consumed);
+                    self.buffer_offset += consumed;
+                    self.line_num = line_num;
+                    return Some(Ok((record, position)));
+                }
+                Err(e) => {
+                    let e = e.shift_offsets(self.buffer_offset);
+                    // Skip the rest of the broken record and resume at the next line
+                    self.line_num += self.buffer.matches('\n').count();
+                    self.buffer_offset += self.buffer.len();
+                    self.buffer.clear();
+                    self.quote_scan = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+
+    /// Whether `text`, which continues a quoted field, contains the field's closing quote
+    ///
+    /// Buffered lines end in a line break, so no escape sequence spans the start of
+    /// `text`.
+    fn closes_quoted_field(&self, text: &str) -> bool {
+        let quote = self.parser.quote;
+        let backslash = self.parser.escape_style == EscapeStyle::Backslash;
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if backslash && c == '\\' {
+                chars.next();
+            } else if c == quote {
+                // A doubled quote is an escaped quote
+                if backslash || chars.next() != Some(quote) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Append the next physical line of input to the buffer
+    fn fill_buffer(&mut self) -> Result<usize, EsvError> {
+        let mut line = Vec::new();
+        let read = self.reader.read_until(b'\n', &mut line)?;
+        let line_start = self.bytes_read;
+        self.bytes_read += read;
+
+        if self.parser.lossy_utf8 {
+            self.buffer.push_str(&String::from_utf8_lossy(&line));
+            return Ok(read);
+        }
+        match String::from_utf8(line) {
+            Ok(text) => self.buffer.push_str(&text),
+            Err(e) => {
+                let valid = e.utf8_error().valid_up_to();
+                let len = e.utf8_error().error_len().unwrap_or(read - valid);
+                let line = e.into_bytes();
+                self.buffer
+                    .push_str(std::str::from_utf8(&line[..valid]).unwrap_or_default());
+                let error =
+                    EsvError::invalid_utf8(&self.buffer, self.line_num, line_start + valid, len);
+
+                // Drop the pending record along with the invalid line
+                self.line_num +=
+                    self.buffer.matches('\n').count() + usize::from(line.ends_with(b"\n"));
+                self.buffer.clear();
+                self.buffer_offset = self.bytes_read;
+                self.quote_scan = None;
+                return Err(error);
+            }
+        }
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> Iterator for EsvReader<R> {
+    type Item = Result<Vec<String>, EsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if let Err(e) = self.start() {
+            return Some(Err(e));
+        }
+
+        if self.done {
+            return None;
+        }
+
+        let result = self.read_record(None);
+        if result.is_none() {
+            self.done = true;
+        }
+        self.position = None;
+        result.map(|result| {
+            result.map(|(record, mut position)| {
+                position.index = self.records_read;
+                self.records_read += 1;
+                self.position = Some(position);
+                record
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+
+    use super::*;
+    use crate::HeaderPolicy;
+    use crate::RaggedPolicy;
+    use crate::SeparatorRejection;
+    use crate::Span;
+
+    fn read_all(parser: EsvParser, input: &str) -> Vec<Result<Vec<String>, EsvError>> {
+        EsvReader::from_parser(parser, input.as_bytes()).collect()
+    }
+
+    #[test]
+    fn test_reader_simple_records() {
+        let records: Vec<_> = EsvReader::new("aaa🔥bbb\nccc🔥ddd\n".as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records, vec![vec!["aaa", "bbb"], vec!["ccc", "ddd"]]);
+    }
+
+    #[test]
+    fn test_reader_matches_parser() {
+        let inputs = [
+            "",
+            "\n",
+            "a\n\n",
+            "a🔥b\r\nc🔥d",
+            "a\rb\rc",
+            "\"a\nb\"🔥c\n\"x\"\"y\"🔥z\n",
+            "\"multi\r\nline\r\nfield\"🔥end\r\n",
+            "🔥🔥\n",
+            "a\n\"\"",
+        ];
+        for input in inputs {
+            let expected = EsvParser::new().parse(input).unwrap().records;
+            let records: Vec<_> = EsvReader::new(input.as_bytes())
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(records, expected, "input: {input:?}");
+        }
+
+        // A rejected header row ends the reader like it fails the parse
+        let parser = EsvParser::new()
+            .with_headers(true)
+            .with_header_policy(HeaderPolicy::Reject);
+        let input = "a🔥a\n1🔥2\n3🔥4\n";
+        let expected = parser.parse(input).unwrap_err();
+        let results = read_all(parser, input);
+        assert_eq!(results, vec![Err(expected)]);
+    }
+
+    #[test]
+    fn test_reader_with_headers() {
+        let mut reader = EsvReader::from_parser(
+            EsvParser::new().with_headers(true),
+            "name🔥age\nAlice🔥30\n".as_bytes(),
+        );
+        assert_eq!(
+            reader.headers().unwrap(),
+            Some(&["name".to_string(), "age".to_string()][..])
+        );
+        let records: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(records, vec![vec!["Alice", "30"]]);
+    }
+
+    #[test]
+    fn test_reader_headers_read_lazily_by_iterator() {
+        let mut reader = EsvReader::from_parser(
+            EsvParser::new().with_headers(true),
+            "name\nAlice\n".as_bytes(),
+        );
+        assert_eq!(reader.next(), Some(Ok(vec!["Alice".to_string()])));
+        assert_eq!(reader.headers().unwrap(), Some(&["name".to_string()][..]));
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn test_reader_header_policy() {
+        let input = "a🔥🔥a\n1🔥2🔥3\n";
+        let mut reader = EsvReader::from_parser(
+            EsvParser::new()
+                .with_headers(true)
+                .with_header_policy(HeaderPolicy::Rename),
+            input.as_bytes(),
+        );
+        assert_eq!(
+            reader.headers().unwrap(),
+            Some(&["a".to_string(), "column_2".to_string(), "a_2".to_string()][..])
+        );
+
+        let mut reader = EsvReader::from_parser(
+            EsvParser::new()
+                .with_headers(true)
+                .with_header_policy(HeaderPolicy::Reject),
+            input.as_bytes(),
+        );
+        assert!(matches!(
+            reader.headers(),
+            Err(EsvError::EmptyHeader {
+                field: 2,
+                span: Span {
+                    start: 5,
+                    end: 5,
+                    grapheme_column: 3
+                },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_reader_comments_and_blank_lines() {
+        let input =
+            "#!esv header=true\n# units\nname🔥unit\n\n# metric\r\n\"#\"🔥x\n\r\nlength🔥m\n# end";
+        let parser = EsvParser::new()
+            .with_comment_prefix("#")
+            .with_skip_blank_lines(true)
+            .with_strict_field_count(true);
+        let mut reader = EsvReader::from_parser(parser.clone(), input.as_bytes());
+        assert_eq!(
+            reader.headers().unwrap(),
+            Some(&["name".to_string(), "unit".to_string()][..])
+        );
+        assert_eq!(reader.next().unwrap().unwrap(), vec!["#", "x"]);
+        assert_eq!(reader.position().map(|p| (p.line, p.offset)), Some((6, 50)));
+        assert_eq!(reader.next().unwrap().unwrap(), vec!["length", "m"]);
+        assert!(reader.next().is_none());
+
+        let doc = parser.parse(input).unwrap();
+        assert_eq!(doc.records, vec![vec!["#", "x"], vec!["length", "m"]]);
+    }
+
+    #[test]
+    fn test_reader_pragma() {
+        let mut reader =
+            EsvReader::new("#!esv sep=🌟 header=true\nname🌟age\nAlice🌟30\n".as_bytes());
+        assert_eq!(
+            reader.headers().unwrap(),
+            Some(&["name".to_string(), "age".to_string()][..])
+        );
+        assert_eq!(
+            reader.next(),
+            Some(Ok(vec!["Alice".to_string(), "30".to_string()]))
+        );
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn test_reader_pragma_required() {
+        let parser = EsvParser::new().with_pragma_mode(PragmaMode::Require);
+        assert_eq!(
+            read_all(parser.clone(), "a🔥b\n"),
+            vec![Err(EsvError::MissingPragma)]
+        );
+        assert_eq!(
+            read_all(parser, "#!esv\na🔥b\n"),
+            vec![Ok(vec!["a".to_string(), "b".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_reader_invalid_separator() {
+        let results = read_all(EsvParser::new().with_separator(','), "a,b\n");
+        assert_eq!(
+            results,
+            vec![Err(EsvError::InvalidSeparator {
+                separator: ",".to_string(),
+                reason: SeparatorRejection::NotEmoji,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_reader_unclosed_quote() {
+        let results = read_all(EsvParser::new(), "ok\n\"never\nclosed\n");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0], Ok(vec!["ok".to_string()]));
+        assert!(matches!(
+            results[1],
+            Err(EsvError::UnclosedQuote { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_reader_continues_after_error() {
+        let results = read_all(EsvParser::new(), "\"bad\"x🔥1\ngood🔥2\n");
+        assert!(matches!(
+            results[0],
+            Err(EsvError::UnexpectedCharAfterQuote { line: 1, .. })
+        ));
+        assert_eq!(results[1], Ok(vec!["good".to_string(), "2".to_string()]));
+    }
+
+    #[test]
+    fn test_reader_strict_field_count() {
+        let results = read_all(
+            EsvParser::new().with_strict_field_count(true),
+            "a🔥b\nc🔥d\ne\n",
+        );
+        assert_eq!(
+            results[2],
+            Err(EsvError::InconsistentFieldCount {
+                expected: 2,
+                found: 1,
+                line: 3,
+                end_line: 3,
+                span: Span {
+                    start: 14,
+                    end: 15,
+                    grapheme_column: 1,
+                },
+            })
+        );
+
+        let mut reader = EsvReader::from_parser(
+            EsvParser::new()
+                .with_strict_field_count(true)
+                .with_headers(true),
+            "x🔥y\n1🔥2\n3\n".as_bytes(),
+        );
+        assert!(matches!(reader.next(), Some(Ok(_))));
+        assert!(matches!(
+            reader.next(),
+            Some(Err(EsvError::HeaderMismatch { line: 3, ref missing, .. })) if missing == &["y"]
+        ));
+
+        let results = read_all(
+            EsvParser::new()
+                .with_strict_field_count(true)
+                .with_headers(true),
+            "name🔥age\nAlice🔥30\n",
+        );
+        assert_eq!(
+            results,
+            vec![Ok(vec!["Alice".to_string(), "30".to_string()])]
+        );
+    }
+
+    #[test]
+    fn test_reader_ragged_policy() {
+        let input = "x🔥y🔥z\n1\n2🔥3🔥4🔥5\n";
+        let parser = EsvParser::new().with_headers(true);
+        let mut reader = EsvReader::from_parser(
+            parser
+                .clone()
+                .with_ragged_policy(RaggedPolicy::Pad("-".into())),
+            input.as_bytes(),
+        );
+        assert_eq!(reader.headers().unwrap().map(<[String]>::len), Some(3));
+        let records: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(records, vec![vec!["1", "-", "-"], vec!["2", "3", "4", "5"]]);
+
+        let results = read_all(
+            parser.clone().with_ragged_policy(RaggedPolicy::Truncate),
+            input,
+        );
+        assert_eq!(
+            results[1],
+            Ok(vec!["2".to_string(), "3".to_string(), "4".to_string()])
+        );
+
+        let parser = parser.with_ragged_policy(RaggedPolicy::Error);
+        let results = read_all(parser.clone(), input);
+        assert!(matches!(
+            results[0],
+            Err(EsvError::HeaderMismatch { line: 2, .. })
+        ));
+
+        let results = read_all(parser, "x🔥y\n1🔥2\n");
+        assert_eq!(results, vec![Ok(vec!["1".to_string(), "2".to_string()])]);
+    }
+
+    #[test]
+    fn test_reader_multi_line_record_lines() {
+        let results = read_all(
+            EsvParser::new().with_strict_field_count(true),
+            "a🔥b\n\"c\nd\"\ne🔥f\n\"g\"x\n",
+        );
+        assert_eq!(results.len(), 4);
+        assert!(matches!(
+            results[1],
+            Err(EsvError::InconsistentFieldCount {
+                line: 2,
+                end_line: 3,
+                ..
+            })
+        ));
+        assert_eq!(results[2], Ok(vec!["e".to_string(), "f".to_string()]));
+        assert!(matches!(
+            results[3],
+            Err(EsvError::UnexpectedCharAfterQuote { line: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn test_reader_positions_match_parser() {
+        let input = "#!esv header=true\nid🔥note\r\n1🔥\"two\nlines\"\n2🔥x\n\n3🔥y";
+        let doc = EsvParser::new().with_positions(true).parse(input).unwrap();
+
+        let mut reader = EsvReader::new(BufReader::with_capacity(4, Cursor::new(input)));
+        assert_eq!(reader.position(), None);
+        let mut positions = Vec::new();
+        while let Some(record) = reader.next() {
+            record.unwrap();
+            positions.push(reader.position().unwrap());
+        }
+        assert_eq!(Some(positions), doc.positions);
+    }
+
+    #[test]
+    fn test_reader_seek_to_position() {
+        let input = "a🔥b\n\"c\nd\"🔥e\nf🔥g\n";
+        let mut reader = EsvReader::new(Cursor::new(input));
+        reader.next();
+        let second = reader.next().unwrap().unwrap();
+        let position = reader.position().unwrap();
+        assert_eq!(
+            (position.index, position.line, position.end_line),
+            (1, 2, 3)
+        );
+
+        // Seek the underlying stream back to the record and read it again
+        let mut stream = reader.into_inner();
+        stream
+            .seek(SeekFrom::Start(position.offset as u64))
+            .unwrap();
+        let mut text = vec![0; position.len];
+        stream.read_exact(&mut text).unwrap();
+        let mut reader = EsvReader::new(&text[..]);
+        assert_eq!(reader.next(), Some(Ok(second)));
+    }
+
+    #[test]
+    fn test_reader_position_after_error() {
+        let mut reader = EsvReader::new("a\n\"b\"x\nc\n".as_bytes());
+        reader.next();
+        assert!(reader.position().is_some());
+        assert!(reader.next().unwrap().is_err());
+        assert_eq!(reader.position(), None);
+        reader.next();
+        let position = reader.position().unwrap();
+        assert_eq!((position.index, position.line, position.offset), (1, 3, 7));
+    }
+
+    #[test]
+    fn test_reader_small_buffer() {
+        let input = "\"long quoted🔥field\"🔥value\nnext🔥row\n";
+        let reader = EsvReader::new(BufReader::with_capacity(3, Cursor::new(input)));
+        let records: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            records,
+            vec![vec!["long quoted🔥field", "value"], vec!["next", "row"]]
+        );
+    }
+
+    #[test]
+    fn test_reader_quoted_field_over_many_lines() {
+        // Lines inside an open quoted field are scanned once, not re-parsed per line
+        let mut input = String::from("a🔥\"open\n");
+        for i in 0..20_000 {
+            let _ = writeln!(input, "line {i}🔥\"\"x\"\"");
+        }
+        let results = read_all(EsvParser::new(), &input);
+        assert!(matches!(
+            results.as_slice(),
+            [Err(EsvError::UnclosedQuote { line: 1, .. })]
+        ));
+
+        input.push_str("end\"🔥b\nc🔥d\n");
+        let expected = EsvParser::new().parse(&input).unwrap().records;
+        let records: Vec<_> = EsvReader::new(input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records, expected);
+
+        let parser = EsvParser::new().with_escape_style(EscapeStyle::Backslash);
+        let input = "\"a\n\\\"b\\\\\n\\\\\"🔥c\nd🔥\"e\n\"\n";
+        let expected = parser.parse(input).unwrap().records;
+        assert_eq!(expected, vec![vec!["a\n\"b\\\n\\", "c"], vec!["d", "e\n"]]);
+        let records: Vec<_> = EsvReader::from_parser(parser, input.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records, expected);
+    }
+
+    #[test]
+    fn test_reader_invalid_utf8() {
+        let input: &[u8] = b"ok\nab\xff\xfe\nnext\n";
+        let results: Vec<_> = EsvReader::new(input).collect();
+        assert_eq!(results[0], Ok(vec!["ok".to_string()]));
+        assert_eq!(
+            results[1],
+            Err(EsvError::InvalidUtf8 {
+                line: 2,
+                column: 3,
+                offset: 5,
+                span: Span {
+                    start: 5,
+                    end: 6,
+                    grapheme_column: 3,
+                },
+            })
+        );
+        assert_eq!(results[2], Ok(vec!["next".to_string()]));
+    }
+
+    #[test]
+    fn test_reader_invalid_utf8_in_multi_line_field() {
+        let input: &[u8] = b"\"a\nb\xff\"\n";
+        let results: Vec<_> = EsvReader::new(input).collect();
+        assert_eq!(
+            results,
+            vec![Err(EsvError::InvalidUtf8 {
+                line: 2,
+                column: 2,
+                offset: 4,
+                span: Span {
+                    start: 4,
+                    end: 5,
+                    grapheme_column: 2,
+                },
+            })]
+        );
+    }
+
+    #[test]
+    fn test_reader_lossy_utf8() {
+        let input: &[u8] = b"a\xff\xf0\x9f\x94\xa5b\n";
+        let records: Vec<_> = EsvReader::from_parser(EsvParser::new().with_lossy_utf8(true), input)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(records, vec![vec!["a\u{FFFD}", "b"]]);
+    }
+
+    #[test]
+    fn test_reader_io_error() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+
+        let results: Vec<_> = EsvReader::new(BufReader::new(FailingReader)).collect();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(
+            &results[0],
+            Err(EsvError::Io { kind: io::ErrorKind::Other, message }) if message == "disk on fire"
+        ));
+
+        // A failure after the first line ends the iterator instead of repeating forever
+        let input = BufReader::new(b"a\n".chain(FailingReader));
+        let results: Vec<_> = EsvReader::new(input).collect();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(
+            &results[1],
+            Err(EsvError::Io { kind: io::ErrorKind::Other, message }) if message == "disk on fire"
+        ));
+    }
+}
@@ -4,16 +4,32 @@
 //! adapted for emoji separators.
 
 use crate::error::EsvError;
-use crate::validate_separator;
+use crate::is_grapheme_boundary;
+use crate::quoting::{validate_quote, DEFAULT_QUOTE};
+use crate::EscapeStyle;
+use crate::EsvDialect;
 use crate::EsvDocument;
+use crate::EsvPragma;
+use crate::SeparatorPolicy;
 use crate::DEFAULT_SEPARATOR;
+use crate::PRAGMA_PREFIX;
+
+/// Prefix of comment lines written by `EsvSerializer::with_comment` when no comment
+/// prefix is set
+const DEFAULT_COMMENT_PREFIX: &str = "#";
 
 /// Serializer for ESV data
 #[derive(Debug, Clone)]
 pub struct EsvSerializer {
-    separator: char,
-    always_quote: bool,
-    line_ending: LineEnding,
+    pub(crate) separator: String,
+    pub(crate) separator_policy: SeparatorPolicy,
+    pub(crate) always_quote: bool,
+    pub(crate) line_ending: LineEnding,
+    pub(crate) pragma: bool,
+    pub(crate) comment: Option<String>,
+    pub(crate) comment_prefix: Option<String>,
+    pub(crate) quote: char,
+    pub(crate) escape_style: EscapeStyle,
 }
 
 /// Line ending style for serialized output
@@ -25,6 +41,17 @@ pub enum LineEnding {
     Crlf,
 }
 
+impl LineEnding {
+    /// The line break written for this style
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
 impl Default for EsvSerializer {
     fn default() -> Self {
         Self::new()
@@ -36,19 +63,52 @@ impl EsvSerializer {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            separator: DEFAULT_SEPARATOR,
+            separator: DEFAULT_SEPARATOR.to_string(),
+            separator_policy: SeparatorPolicy::StrictEmoji,
             always_quote: false,
             line_ending: LineEnding::Lf,
+            pragma: false,
+            comment: None,
+            comment_prefix: None,
+            quote: DEFAULT_QUOTE,
+            escape_style: EscapeStyle::Doubled,
+        }
+    }
+
+    /// Create a serializer configured with the settings of a dialect
+    #[must_use]
+    pub fn from_dialect(dialect: &EsvDialect) -> Self {
+        Self {
+            separator: dialect.separator.clone(),
+            separator_policy: dialect.separator_policy.clone(),
+            always_quote: dialect.always_quote,
+            line_ending: dialect.line_ending,
+            quote: dialect.quote,
+            escape_style: dialect.escape_style,
+            comment_prefix: dialect.comment_prefix.clone().filter(|p| !p.is_empty()),
+            ..Self::new()
         }
     }
 
     /// Set a custom emoji separator
     ///
+    /// The separator may be a single emoji (`'😀'`) or a full emoji sequence such as
+    /// a ZWJ sequence, flag or skin-tone variant (`"🏳️‍🌈"`, `"🇺🇸"`, `"👍🏽"`).
+    ///
     /// Note: The separator will be validated when `serialize()` or `try_serialize()` is called.
-    /// Only emoji characters are allowed as separators.
+    /// Only emoji are allowed as separators.
     #[must_use]
-    pub fn with_separator(mut self, separator: char) -> Self {
-        self.separator = separator;
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Set the policy deciding which separators are accepted
+    ///
+    /// Defaults to `SeparatorPolicy::StrictEmoji`.
+    #[must_use]
+    pub fn with_separator_policy(mut self, policy: SeparatorPolicy) -> Self {
+        self.separator_policy = policy;
         self
     }
 
@@ -59,58 +119,163 @@ impl EsvSerializer {
         self
     }
 
-    /// Set the line ending style
+    /// Set the line ending written after each record
+    ///
+    /// Line breaks inside fields are written unchanged, unless escaped with
+    /// `EscapeStyle::Backslash`.
     #[must_use]
     pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
         self.line_ending = line_ending;
         self
     }
 
+    /// Emit a leading `#!esv` pragma line declaring the separator, header presence and
+    /// line ending
+    #[must_use]
+    pub fn with_pragma(mut self, pragma: bool) -> Self {
+        self.pragma = pragma;
+        self
+    }
+
+    /// Set the character that encloses quoted fields (default: `"`)
+    ///
+    /// The quote must be ASCII punctuation that is neither part of the separator nor
+    /// the start of the comment prefix; it is validated when serializing.
+    #[must_use]
+    pub fn with_quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Set how quotes are escaped inside quoted fields
+    ///
+    /// Defaults to `EscapeStyle::Doubled`. With `EscapeStyle::Backslash`, backslashes
+    /// and line breaks inside quoted fields are written as `\\`, `\n` and `\r`, so
+    /// every record stays on one line.
+    #[must_use]
+    pub fn with_escape_style(mut self, escape_style: EscapeStyle) -> Self {
+        self.escape_style = escape_style;
+        self
+    }
+
+    /// Emit a leading comment block, one comment line per line of `text`
+    ///
+    /// The block follows the pragma line, if any. Lines are split at CR, LF and CRLF
+    /// alike and written behind the comment prefix (`#` unless set). Records whose
+    /// first field starts with the prefix are quoted so comment-aware parsers read
+    /// them as data.
+    #[must_use]
+    pub fn with_comment(mut self, text: impl Into<String>) -> Self {
+        self.comment = Some(text.into());
+        self
+    }
+
+    /// Set the comment prefix (such as `#`) of the parsers reading the output
+    ///
+    /// Records whose first field starts with the prefix are quoted, with or without a
+    /// comment block, and `with_comment()` lines are written behind it. Unset by
+    /// default; an empty prefix unsets it, as with `EsvParser::with_comment_prefix`.
+    #[must_use]
+    pub fn with_comment_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.comment_prefix = Some(prefix.into()).filter(|prefix| !prefix.is_empty());
+        self
+    }
+
+    /// The prefix of comment lines in the output, if it can have any
+    fn active_comment_prefix(&self) -> Option<&str> {
+        match (&self.comment_prefix, &self.comment) {
+            (Some(prefix), _) => Some(prefix),
+            (None, Some(_)) => Some(DEFAULT_COMMENT_PREFIX),
+            (None, None) => None,
+        }
+    }
+
     /// Serialize an ESV document to a string
     ///
     /// # Panics
     ///
-    /// Panics if the separator is not an emoji. Use `try_serialize()` for a non-panicking version.
+    /// Panics if the separator is rejected by the separator policy or the quote
+    /// character is invalid. Use `try_serialize()` for a non-panicking version.
     #[must_use]
     pub fn serialize(&self, doc: &EsvDocument) -> String {
-        self.try_serialize(doc).expect("separator must be an emoji")
+        self.try_serialize(doc)
+            .expect("separator and quote character must be valid")
     }
 
     /// Try to serialize an ESV document to a string
     ///
     /// # Errors
     ///
-    /// Returns `EsvError::InvalidSeparator` if the separator is not an emoji.
+    /// Returns `EsvError::InvalidSeparator` if the separator is rejected by the separator
+    /// policy, or `EsvError::InvalidQuote` if the quote character is invalid.
     pub fn try_serialize(&self, doc: &EsvDocument) -> Result<String, EsvError> {
-        // Validate separator is an emoji
-        validate_separator(self.separator)?;
+        self.validate()?;
 
         Ok(self.serialize_internal(doc))
     }
 
-    /// Internal serialization (assumes separator is already validated)
+    /// Validate the separator against the policy, and the quote character
+    pub(crate) fn validate(&self) -> Result<(), EsvError> {
+        self.separator_policy.validate(&self.separator)?;
+        validate_quote(
+            self.quote,
+            self.escape_style,
+            &self.separator,
+            self.active_comment_prefix(),
+        )
+    }
+
+    /// Internal serialization (assumes separator and quote are already validated)
     fn serialize_internal(&self, doc: &EsvDocument) -> String {
         let mut output = String::new();
-        let line_ending = match self.line_ending {
-            LineEnding::Lf => "\n",
-            LineEnding::Crlf => "\r\n",
-        };
+        let line_ending = self.line_ending.as_str();
+
+        self.serialize_preamble(doc.headers.is_some(), &mut output);
 
         // Serialize headers if present
         if let Some(headers) = &doc.headers {
-            self.serialize_record(headers, &mut output);
+            self.serialize_record(headers, true, &mut output);
             output.push_str(line_ending);
         }
 
         // Serialize records
-        for record in &doc.records {
-            self.serialize_record(record, &mut output);
+        for (i, record) in doc.records.iter().enumerate() {
+            let first_row = i == 0 && doc.headers.is_none();
+            self.serialize_record(record, first_row, &mut output);
             output.push_str(line_ending);
         }
 
         output
     }
 
+    /// Write the pragma line and comment block that precede the first row
+    pub(crate) fn serialize_preamble(&self, has_headers: bool, output: &mut String) {
+        let line_ending = self.line_ending.as_str();
+        if self.pragma {
+            output.push_str(&self.pragma_line(has_headers).to_string());
+            output.push_str(line_ending);
+        }
+        if let (Some(comment), Some(prefix)) = (&self.comment, self.active_comment_prefix()) {
+            for line in comment_lines(comment) {
+                output.push_str(prefix);
+                if !line.is_empty() {
+                    output.push(' ');
+                    output.push_str(line);
+                }
+                output.push_str(line_ending);
+            }
+        }
+    }
+
+    /// The pragma describing this serializer's output
+    pub(crate) fn pragma_line(&self, has_headers: bool) -> EsvPragma {
+        EsvPragma {
+            separator: Some(self.separator.clone()),
+            has_headers: Some(has_headers),
+            line_ending: Some(self.line_ending),
+        }
+    }
+
     /// Serialize records without headers
     #[must_use]
     pub fn serialize_records(&self, records: &[Vec<String>]) -> String {
@@ -118,45 +283,106 @@ impl EsvSerializer {
     }
 
     /// Serialize a single record (row)
-    fn serialize_record(&self, record: &[String], output: &mut String) {
-        for (i, field) in record.iter().enumerate() {
+    ///
+    /// On the first row of the output, a first field starting with `#!esv` is quoted
+    /// so it is not read back as a pragma line.
+    pub(crate) fn serialize_record<I, T>(&self, record: I, first_row: bool, output: &mut String)
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        for (i, field) in record.into_iter().enumerate() {
+            let field = field.as_ref();
             if i > 0 {
-                output.push(self.separator);
+                output.push_str(&self.separator);
             }
-            self.serialize_field(field, output);
+            // A first field starting with the comment prefix would read as a comment
+            let is_comment = i == 0
+                && self
+                    .active_comment_prefix()
+                    .is_some_and(|prefix| field.starts_with(prefix));
+            let is_pragma = i == 0 && first_row && field.starts_with(PRAGMA_PREFIX);
+            self.serialize_field(field, is_comment || is_pragma, output);
         }
     }
 
-    /// Serialize a single field, quoting if necessary
-    fn serialize_field(&self, field: &str, output: &mut String) {
-        let needs_quoting = self.always_quote || self.field_needs_quoting(field);
+    /// Serialize a single field, quoting if necessary or if `force_quote` is set
+    fn serialize_field(&self, field: &str, force_quote: bool, output: &mut String) {
+        let needs_quoting = force_quote || self.always_quote || self.field_needs_quoting(field);
 
         if needs_quoting {
-            output.push('"');
+            output.push(self.quote);
             for c in field.chars() {
-                if c == '"' {
-                    output.push_str("\"\"");
-                } else {
-                    output.push(c);
+                match (c, self.escape_style) {
+                    (c, EscapeStyle::Doubled) if c == self.quote => {
+                        output.push(c);
+                        output.push(c);
+                    }
+                    (c, EscapeStyle::Backslash) if c == self.quote || c == '\\' => {
+                        output.push('\\');
+                        output.push(c);
+                    }
+                    ('\n', EscapeStyle::Backslash) => output.push_str("\\n"),
+                    ('\r', EscapeStyle::Backslash) => output.push_str("\\r"),
+                    (c, _) => output.push(c),
                 }
             }
-            output.push('"');
+            output.push(self.quote);
         } else {
             output.push_str(field);
         }
     }
 
     /// Check if a field needs to be quoted
+    ///
+    /// Besides the RFC 4180 rules, fields are quoted when a grapheme cluster embeds the
+    /// separator (❤️‍🔥 with 🔥) or when the field's edges would fuse with an adjacent
+    /// separator into one cluster (a leading skin tone or combining mark).
     fn field_needs_quoting(&self, field: &str) -> bool {
-        field
-            .chars()
-            .any(|c| c == self.separator || c == '"' || c == '\n' || c == '\r')
+        field.contains(self.separator.as_str())
+            || field
+                .chars()
+                .any(|c| c == self.quote || c == '\n' || c == '\r')
+            || self.field_fuses_with_separator(field)
+    }
+
+    /// Check if writing the separator next to this field would stop it from being a
+    /// standalone grapheme cluster
+    fn field_fuses_with_separator(&self, field: &str) -> bool {
+        let (Some(first), Some(last)) = (field.chars().next(), field.chars().next_back()) else {
+            return false;
+        };
+        // ASCII never extends or joins an adjacent emoji cluster
+        if first.is_ascii() && last.is_ascii() {
+            return false;
+        }
+
+        let joined = format!("{0}{field}{0}", self.separator);
+        let start = self.separator.len();
+        !is_grapheme_boundary(&joined, start) || !is_grapheme_boundary(&joined, start + field.len())
     }
 }
 
+/// Lines of a comment block, split at CR, LF and CRLF as the parser splits lines
+///
+/// As with `str::lines()`, a final line break does not start another line.
+fn comment_lines(text: &str) -> Vec<&str> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let text = text
+        .strip_suffix("\r\n")
+        .or_else(|| text.strip_suffix(['\r', '\n']))
+        .unwrap_or(text);
+    text.split("\r\n")
+        .flat_map(|line| line.split(['\r', '\n']))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::EsvParser;
 
     #[test]
     fn test_serialize_simple() {
@@ -232,6 +458,20 @@ mod tests {
         assert_eq!(output, "aaa🔥bbb\r\n");
     }
 
+    #[test]
+    fn test_serialize_keeps_embedded_line_breaks() {
+        let doc = EsvDocument::new(vec![vec!["a\r\nb".to_string(), "c\rd\ne".to_string()]]);
+        for (line_ending, end) in [(LineEnding::Lf, "\n"), (LineEnding::Crlf, "\r\n")] {
+            let output = EsvSerializer::new()
+                .with_line_ending(line_ending)
+                .serialize(&doc);
+            assert_eq!(output, format!("\"a\r\nb\"🔥\"c\rd\ne\"{end}"));
+
+            let parser = EsvParser::new().with_preserve_line_endings(true);
+            assert_eq!(parser.parse(&output).unwrap(), doc);
+        }
+    }
+
     #[test]
     fn test_serialize_custom_separator() {
         let serializer = EsvSerializer::new().with_separator('😀');
@@ -240,6 +480,216 @@ mod tests {
         assert_eq!(output, "aaa😀bbb\n");
     }
 
+    #[test]
+    fn test_serialize_multi_codepoint_separator() {
+        let serializer = EsvSerializer::new().with_separator("👍🏽");
+        let doc = EsvDocument::new(vec![vec![
+            "a👍b".to_string(),
+            "c👍🏽d".to_string(),
+            "e".to_string(),
+        ]]);
+        let output = serializer.serialize(&doc);
+        assert_eq!(output, "a👍b👍🏽\"c👍🏽d\"👍🏽e\n");
+    }
+
+    #[test]
+    fn test_serialize_quotes_embedded_separator_code_point() {
+        let serializer = EsvSerializer::new();
+        let doc = EsvDocument::new(vec![vec!["❤️‍🔥".to_string(), "ok".to_string()]]);
+        let output = serializer.serialize(&doc);
+        assert_eq!(output, "\"❤️‍🔥\"🔥ok\n");
+    }
+
+    #[test]
+    fn test_serialize_quotes_fields_fusing_with_separator() {
+        let serializer = EsvSerializer::new();
+        let doc = EsvDocument::new(vec![vec![
+            "a".to_string(),
+            "🏽b".to_string(),
+            "😀\u{200D}".to_string(),
+            "\u{301}".to_string(),
+            "日本".to_string(),
+        ]]);
+        let output = serializer.serialize(&doc);
+        assert_eq!(output, "a🔥\"🏽b\"🔥\"😀\u{200D}\"🔥\"\u{301}\"🔥日本\n");
+    }
+
+    #[test]
+    fn test_serialize_separator_policy() {
+        let doc = EsvDocument::new(vec![vec!["a".to_string(), "b".to_string()]]);
+        let serializer = EsvSerializer::new()
+            .with_separator('→')
+            .with_separator_policy(SeparatorPolicy::Permissive);
+        assert_eq!(serializer.try_serialize(&doc).unwrap(), "a→b\n");
+
+        let serializer =
+            EsvSerializer::new().with_separator_policy(SeparatorPolicy::allowlist(["⭐"]));
+        assert!(matches!(
+            serializer.try_serialize(&doc),
+            Err(EsvError::InvalidSeparator {
+                reason: crate::SeparatorRejection::NotAllowlisted,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_serialize_with_pragma() {
+        let serializer = EsvSerializer::new()
+            .with_separator('🌟')
+            .with_line_ending(LineEnding::Crlf)
+            .with_pragma(true);
+        let doc =
+            EsvDocument::with_headers(vec!["name".to_string()], vec![vec!["Alice".to_string()]]);
+        let output = serializer.serialize(&doc);
+        assert_eq!(
+            output,
+            "#!esv sep=🌟 header=true eol=crlf\r\nname\r\nAlice\r\n"
+        );
+
+        // The pragma lets a default parser read the output back
+        assert_eq!(crate::EsvParser::new().parse(&output).unwrap(), doc);
+    }
+
+    #[test]
+    fn test_serialize_pragma_like_first_field() {
+        let records = vec![
+            vec!["#!esv note".to_string(), "x".to_string()],
+            vec!["#!esv".to_string(), "b".to_string()],
+        ];
+        let output = EsvSerializer::new().serialize_records(&records);
+        assert_eq!(output, "\"#!esv note\"🔥x\n#!esv🔥b\n");
+        assert_eq!(EsvParser::new().parse(&output).unwrap().records, records);
+
+        let doc =
+            EsvDocument::with_headers(vec!["#!esv".to_string()], vec![vec!["#!esv".to_string()]]);
+        let output = EsvSerializer::new().serialize(&doc);
+        assert_eq!(output, "\"#!esv\"\n#!esv\n");
+        let parser = EsvParser::new().with_headers(true);
+        assert_eq!(parser.parse(&output).unwrap(), doc);
+    }
+
+    #[test]
+    fn test_serialize_with_comment() {
+        let doc = EsvDocument::with_headers(
+            vec!["name".to_string(), "unit".to_string()],
+            vec![vec!["#1".to_string(), "m".to_string()]],
+        );
+        let serializer = EsvSerializer::new()
+            .with_pragma(true)
+            .with_comment("Units table\n\nMaintained by hand");
+        let output = serializer.serialize(&doc);
+        assert_eq!(
+            output,
+            "#!esv sep=🔥 header=true eol=lf\n# Units table\n#\n# Maintained by hand\nname🔥unit\n\"#1\"🔥m\n"
+        );
+        let parser = EsvParser::new().with_comment_prefix("#");
+        assert_eq!(parser.parse(&output).unwrap(), doc);
+
+        let output = EsvSerializer::new()
+            .with_comment_prefix("//")
+            .with_comment("generated")
+            .serialize(&doc);
+        assert_eq!(output, "// generated\nname🔥unit\n#1🔥m\n");
+
+        // Without a comment prefix, fields are quoted as usual
+        assert_eq!(EsvSerializer::new().serialize(&doc), "name🔥unit\n#1🔥m\n");
+        let output = EsvSerializer::new()
+            .with_comment_prefix("#")
+            .serialize(&doc);
+        assert_eq!(output, "name🔥unit\n\"#1\"🔥m\n");
+        assert_eq!(parser.parse(&output).unwrap().records.len(), 2);
+
+        // An empty prefix is unset, so the block falls back to `#`
+        let output = EsvSerializer::new()
+            .with_comment_prefix("")
+            .with_comment("hello")
+            .serialize(&doc);
+        assert_eq!(output, "# hello\nname🔥unit\n\"#1\"🔥m\n");
+
+        // Every kind of line break starts a new comment line
+        let output = EsvSerializer::new()
+            .with_comment("a\rinjected🔥row\r\nb\nc\n")
+            .serialize(&EsvDocument::new(vec![vec!["x".to_string()]]));
+        assert_eq!(output, "# a\n# injected🔥row\n# b\n# c\nx\n");
+        assert_eq!(parser.parse(&output).unwrap().records, vec![vec!["x"]]);
+    }
+
+    #[test]
+    fn test_serialize_quote_and_escape_style() {
+        let doc = EsvDocument::new(vec![vec![
+            "it's".to_string(),
+            "say \"hi\"".to_string(),
+            "C:\\dir\nnext".to_string(),
+        ]]);
+        let output = EsvSerializer::new().with_quote('\'').serialize(&doc);
+        assert_eq!(output, "'it''s'🔥say \"hi\"🔥'C:\\dir\nnext'\n");
+
+        let output = EsvSerializer::new()
+            .with_escape_style(EscapeStyle::Backslash)
+            .serialize(&doc);
+        assert_eq!(output, "it's🔥\"say \\\"hi\\\"\"🔥\"C:\\\\dir\\nnext\"\n");
+
+        assert_eq!(
+            EsvSerializer::new().with_quote('🔥').try_serialize(&doc),
+            Err(EsvError::InvalidQuote {
+                quote: '🔥',
+                reason: "must be ASCII punctuation".to_string(),
+            })
+        );
+        assert!(matches!(
+            EsvSerializer::new()
+                .with_quote('#')
+                .with_comment("note")
+                .try_serialize(&doc),
+            Err(EsvError::InvalidQuote { quote: '#', .. })
+        ));
+    }
+
+    #[test]
+    fn test_quote_and_escape_style_roundtrip() {
+        let records = vec![
+            vec!["plain", "", "it's", "say \"hi\""],
+            vec!["a🔥b", "❤️‍🔥", "🏳️‍🌈 flag", "back\\slash \\n \\"],
+            vec!["line\nbreak", "cr\r\nlf", "'\"'\"", "`tick`"],
+        ];
+        let records: Vec<Vec<String>> = records
+            .into_iter()
+            .map(|record| record.into_iter().map(String::from).collect())
+            .collect();
+        let doc = EsvDocument::new(records.clone());
+
+        for separator in ["🔥", "🏳️‍🌈", "👍🏽", "🇺🇸"] {
+            for quote in ['"', '\'', '`'] {
+                for escape_style in [EscapeStyle::Doubled, EscapeStyle::Backslash] {
+                    for always_quote in [false, true] {
+                        let output = EsvSerializer::new()
+                            .with_separator(separator)
+                            .with_quote(quote)
+                            .with_escape_style(escape_style)
+                            .with_always_quote(always_quote)
+                            .serialize(&doc);
+                        let parser = EsvParser::new()
+                            .with_separator(separator)
+                            .with_quote(quote)
+                            .with_escape_style(escape_style)
+                            .with_preserve_line_endings(true);
+                        let context = format!("{separator} {quote} {escape_style:?}: {output:?}");
+                        assert_eq!(parser.parse(&output).unwrap(), doc, "{context}");
+
+                        let read: Vec<_> = crate::EsvReader::from_parser(parser, output.as_bytes())
+                            .collect::<Result<_, _>>()
+                            .unwrap();
+                        assert_eq!(read, records, "{context}");
+                        if escape_style == EscapeStyle::Backslash {
+                            assert_eq!(output.lines().count(), 3, "{context}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_serialize_empty_fields() {
         let serializer = EsvSerializer::new();
@@ -0,0 +1,409 @@
+//! ESV dialect sniffer
+//!
+//! Guesses the separator, header presence and line ending of ESV data whose
+//! dialect is unknown, by sampling the first records of the input.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::is_emoji_sequence;
+use crate::quoting::DEFAULT_QUOTE;
+use crate::EsvDialect;
+use crate::EsvParser;
+use crate::LineEnding;
+use crate::SeparatorPolicy;
+use crate::DEFAULT_SEPARATOR;
+
+/// Default number of records sampled by `EsvSniffer`
+const DEFAULT_SAMPLE_RECORDS: usize = 100;
+
+/// Detects the dialect of ESV data
+///
+/// Candidate separators are scored by how consistently they occur per record outside
+/// quoted fields: the candidate whose most common per-record count is shared by the
+/// most records wins, with ties going to the candidate producing more fields.
+#[derive(Debug, Clone)]
+pub struct EsvSniffer {
+    sample_records: usize,
+    candidates: Option<Vec<String>>,
+    quote: char,
+}
+
+/// The dialect detected by `EsvSniffer`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SniffResult {
+    /// Detected separator (the default separator if no candidate occurs)
+    pub separator: String,
+    /// Whether the first record looks like a header row
+    pub has_headers: bool,
+    /// Predominant line ending outside quoted fields
+    pub line_ending: LineEnding,
+}
+
+impl SniffResult {
+    /// Build a dialect with the detected separator, header presence and line ending
+    ///
+    /// A non-emoji separator (only possible with custom candidates) is allowlisted
+    /// so parsers built from the dialect accept it.
+    #[must_use]
+    pub fn dialect(&self) -> EsvDialect {
+        let separator_policy = if is_emoji_sequence(&self.separator) {
+            SeparatorPolicy::StrictEmoji
+        } else {
+            SeparatorPolicy::allowlist([self.separator.as_str()])
+        };
+        EsvDialect {
+            separator: self.separator.clone(),
+            separator_policy,
+            has_headers: self.has_headers,
+            line_ending: self.line_ending,
+            ..EsvDialect::new()
+        }
+    }
+
+    /// Build a parser configured with the detected separator and header presence
+    #[must_use]
+    pub fn parser(&self) -> EsvParser {
+        self.dialect().parser()
+    }
+}
+
+impl Default for EsvSniffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EsvSniffer {
+    /// Create a new sniffer with default settings
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            sample_records: DEFAULT_SAMPLE_RECORDS,
+            candidates: None,
+            quote: DEFAULT_QUOTE,
+        }
+    }
+
+    /// Set how many records to sample (default: 100)
+    #[must_use]
+    pub fn with_sample_records(mut self, sample_records: usize) -> Self {
+        self.sample_records = sample_records.max(1);
+        self
+    }
+
+    /// Restrict detection to a fixed set of candidate separators
+    ///
+    /// By default every emoji or emoji sequence found in the sample is a candidate.
+    #[must_use]
+    pub fn with_candidates<I, S>(mut self, candidates: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.candidates = Some(candidates.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the character that encloses quoted fields (default: `"`)
+    ///
+    /// Candidate separators inside quoted fields are not counted.
+    #[must_use]
+    pub fn with_quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Detect the dialect of the input
+    #[must_use]
+    pub fn sniff(&self, input: &str) -> SniffResult {
+        let sample = self.sample(input);
+        let separator =
+            detect_separator(&sample.counts).unwrap_or_else(|| DEFAULT_SEPARATOR.to_string());
+        let has_headers = detect_headers(sample.text, &separator);
+        let line_ending = if sample.crlf > sample.lf {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        };
+
+        SniffResult {
+            separator,
+            has_headers,
+            line_ending,
+        }
+    }
+
+    /// Detect the dialect of the input and return a parser configured for it
+    #[must_use]
+    pub fn sniff_parser(&self, input: &str) -> EsvParser {
+        self.sniff(input).parser()
+    }
+
+    /// Collect per-record candidate counts and line ending statistics
+    fn sample<'a>(&self, input: &'a str) -> Sample<'a> {
+        let mut sample = Sample {
+            text: input,
+            counts: Vec::new(),
+            crlf: 0,
+            lf: 0,
+        };
+        let mut record: HashMap<&'a str, usize> = HashMap::new();
+        let mut in_quotes = false;
+        let mut pending = false;
+
+        for (offset, grapheme) in input.grapheme_indices(true) {
+            match grapheme {
+                "\r\n" | "\n" | "\r" if !in_quotes => {
+                    match grapheme {
+                        "\r\n" => sample.crlf += 1,
+                        "\n" => sample.lf += 1,
+                        _ => {}
+                    }
+                    sample.counts.push(std::mem::take(&mut record));
+                    pending = false;
+                    if sample.counts.len() == self.sample_records {
+                        sample.text = &input[..offset];
+                        return sample;
+                    }
+                    continue;
+                }
+                _ if self.is_quote(grapheme) => in_quotes = !in_quotes,
+                _ if !in_quotes && self.is_candidate(grapheme) => {
+                    *record.entry(grapheme).or_insert(0) += 1;
+                }
+                _ => {}
+            }
+            pending = true;
+        }
+
+        // Final record without a trailing line break
+        if pending {
+            sample.counts.push(record);
+        }
+
+        sample
+    }
+
+    fn is_quote(&self, grapheme: &str) -> bool {
+        let mut chars = grapheme.chars();
+        chars.next() == Some(self.quote) && chars.next().is_none()
+    }
+
+    fn is_candidate(&self, grapheme: &str) -> bool {
+        match &self.candidates {
+            Some(candidates) => candidates.iter().any(|c| c == grapheme),
+            None => is_emoji_sequence(grapheme),
+        }
+    }
+}
+
+/// Statistics gathered from the sampled records
+struct Sample<'a> {
+    /// The sampled prefix of the input
+    text: &'a str,
+    /// Candidate separator counts outside quotes, one map per record
+    counts: Vec<HashMap<&'a str, usize>>,
+    crlf: usize,
+    lf: usize,
+}
+
+/// Pick the candidate with the most consistent per-record frequency
+fn detect_separator(counts: &[HashMap<&str, usize>]) -> Option<String> {
+    let mut first_seen: Vec<&str> = Vec::new();
+    for record in counts {
+        for &candidate in record.keys() {
+            if !first_seen.contains(&candidate) {
+                first_seen.push(candidate);
+            }
+        }
+    }
+    // Hash map iteration order is random; order candidates by first record seen,
+    // then by code point so ties are deterministic
+    first_seen.sort_by_key(|c| (counts.iter().position(|r| r.contains_key(c)), c.to_string()));
+
+    first_seen
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, candidate)| {
+            let mut frequencies: HashMap<usize, usize> = HashMap::new();
+            for record in counts {
+                let count = record.get(*candidate).copied().unwrap_or(0);
+                *frequencies.entry(count).or_insert(0) += 1;
+            }
+            let (mode, records_with_mode) = frequencies
+                .into_iter()
+                .filter(|&(count, _)| count > 0)
+                .max_by_key(|&(count, records)| (records, count))
+                .unwrap_or((0, 0));
+            (records_with_mode, mode, Reverse(*index))
+        })
+        .map(|(_, candidate)| (*candidate).to_string())
+}
+
+/// Guess whether the first record is a header row
+///
+/// Each column votes: a column whose data values are all numeric (or all the same
+/// length) while the first value is not suggests a header; a first value that fits
+/// the pattern suggests data.
+fn detect_headers(sample: &str, separator: &str) -> bool {
+    let Ok(doc) = EsvParser::new()
+        .with_separator(separator)
+        .with_separator_policy(SeparatorPolicy::allowlist([separator]))
+        .parse(sample)
+    else {
+        return false;
+    };
+
+    let Some((header, rows)) = doc.records.split_first() else {
+        return false;
+    };
+    if rows.is_empty() || header.iter().any(String::is_empty) {
+        return false;
+    }
+
+    let mut votes = 0i32;
+    for (column, name) in header.iter().enumerate() {
+        let values: Vec<&str> = rows
+            .iter()
+            .filter_map(|row| row.get(column).map(String::as_str))
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+
+        if values.iter().all(|v| is_numeric(v)) {
+            votes += if is_numeric(name) { -1 } else { 1 };
+            continue;
+        }
+
+        let length = values[0].chars().count();
+        if values.iter().all(|v| v.chars().count() == length) {
+            votes += if name.chars().count() == length {
+                -1
+            } else {
+                1
+            };
+        }
+    }
+
+    votes > 0
+}
+
+fn is_numeric(value: &str) -> bool {
+    !value.is_empty() && value.trim().parse::<f64>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_default_separator() {
+        let result = EsvSniffer::new().sniff("a🔥b🔥c\nd🔥e🔥f\n");
+        assert_eq!(result.separator, "🔥");
+        assert!(!result.has_headers);
+        assert_eq!(result.line_ending, LineEnding::Lf);
+    }
+
+    #[test]
+    fn test_sniff_prefers_consistent_candidate() {
+        // 😀 occurs in data on some lines; ⭐ occurs exactly twice on every line
+        let input = "a⭐b😀⭐c\nd⭐e⭐f\ng😀😀⭐h⭐i\nj⭐k⭐l\n";
+        let result = EsvSniffer::new().sniff(input);
+        assert_eq!(result.separator, "⭐");
+    }
+
+    #[test]
+    fn test_sniff_ignores_quoted_fields() {
+        let input = "\"x😀y😀z\"⭐b\n\"😀😀\"⭐d\n\"😀\"⭐f\n";
+        let result = EsvSniffer::new().sniff(input);
+        assert_eq!(result.separator, "⭐");
+    }
+
+    #[test]
+    fn test_sniff_custom_quote() {
+        let input = "'x😀y😀z'⭐b\n'😀😀'⭐d\n'a😀b😀'⭐f\n";
+        let result = EsvSniffer::new().with_quote('\'').sniff(input);
+        assert_eq!(result.separator, "⭐");
+        assert_eq!(EsvSniffer::new().sniff(input).separator, "😀");
+    }
+
+    #[test]
+    fn test_sniff_multi_line_quoted_fields() {
+        let input = "\"line1\nline2\"🌟b\nc🌟\"x\ny\"\n";
+        let result = EsvSniffer::new().sniff(input);
+        assert_eq!(result.separator, "🌟");
+    }
+
+    #[test]
+    fn test_sniff_emoji_sequence_separator() {
+        let input = "a🏳️‍🌈b🏳️‍🌈c\nd🏳️‍🌈e🏳️‍🌈f\n";
+        let result = EsvSniffer::new().sniff(input);
+        assert_eq!(result.separator, "🏳️‍🌈");
+    }
+
+    #[test]
+    fn test_sniff_headers() {
+        let input = "name⭐age\nAlice⭐30\nBob⭐25\n";
+        let result = EsvSniffer::new().sniff(input);
+        assert_eq!(result.separator, "⭐");
+        assert!(result.has_headers);
+
+        let input = "10⭐20\n30⭐40\n";
+        assert!(!EsvSniffer::new().sniff(input).has_headers);
+    }
+
+    #[test]
+    fn test_sniff_crlf() {
+        let result = EsvSniffer::new().sniff("a🔥b\r\nc🔥d\r\n");
+        assert_eq!(result.line_ending, LineEnding::Crlf);
+    }
+
+    #[test]
+    fn test_sniff_no_candidates_falls_back_to_default() {
+        let result = EsvSniffer::new().sniff("just\ntext\n");
+        assert_eq!(result.separator, DEFAULT_SEPARATOR.to_string());
+    }
+
+    #[test]
+    fn test_sniff_with_candidates() {
+        let input = "a§b§c\nd§e§f\n";
+        let result = EsvSniffer::new().with_candidates(["§"]).sniff(input);
+        assert_eq!(result.separator, "§");
+        let doc = result.parser().parse(input).unwrap();
+        assert_eq!(doc.records, vec![vec!["a", "b", "c"], vec!["d", "e", "f"]]);
+    }
+
+    #[test]
+    fn test_sniff_dialect() {
+        let dialect = EsvSniffer::new()
+            .sniff("name🌟age\r\nAlice🌟30\r\n")
+            .dialect();
+        assert_eq!(dialect.separator, "🌟");
+        assert_eq!(dialect.separator_policy, SeparatorPolicy::StrictEmoji);
+        assert!(dialect.has_headers);
+        assert_eq!(dialect.line_ending, LineEnding::Crlf);
+    }
+
+    #[test]
+    fn test_sniff_sample_limit() {
+        let mut input = "a⭐b\n".repeat(3);
+        input.push_str(&"c😀d😀e\n".repeat(10));
+        let result = EsvSniffer::new().with_sample_records(3).sniff(&input);
+        assert_eq!(result.separator, "⭐");
+    }
+
+    #[test]
+    fn test_sniff_parser() {
+        let input = "name🌟age\nAlice🌟30\nBob🌟25\n";
+        let doc = EsvSniffer::new().sniff_parser(input).parse(input).unwrap();
+        assert_eq!(
+            doc.headers,
+            Some(vec!["name".to_string(), "age".to_string()])
+        );
+        assert_eq!(doc.records, vec![vec!["Alice", "30"], vec!["Bob", "25"]]);
+    }
+}
@@ -0,0 +1,158 @@
+//! Non-fatal findings reported alongside parse results
+
+use std::fmt;
+
+use crate::error::render_snippet;
+use crate::error::Span;
+
+/// Input the parser accepted even though it does not conform to the ESV spec
+///
+/// Collected by `EsvParser::parse_with_warnings` and `EsvParser::parse_lenient`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EsvWarning {
+    /// Double quote inside an unquoted field, kept as a literal character (an error
+    /// with `EsvParser::with_strict_syntax`)
+    BareQuote {
+        line: usize,
+        column: usize,
+        span: Span,
+    },
+    /// CR or CRLF line break inside a quoted field, rewritten as LF (reported once
+    /// per field)
+    NormalizedLineBreak {
+        line: usize,
+        column: usize,
+        span: Span,
+    },
+    /// Empty record at the end of the input, such as a lone `""`, left out of the
+    /// document
+    TrailingEmptyRecord { line: usize, span: Span },
+    /// Record ending with a different line break than the first record (reported
+    /// once, at the first such record)
+    MixedLineEndings {
+        line: usize,
+        expected: &'static str,
+        found: &'static str,
+        span: Span,
+    },
+}
+
+impl EsvWarning {
+    /// The part of the input the warning points at
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match self {
+            EsvWarning::BareQuote { span, .. }
+            | EsvWarning::NormalizedLineBreak { span, .. }
+            | EsvWarning::TrailingEmptyRecord { span, .. }
+            | EsvWarning::MixedLineEndings { span, .. } => *span,
+        }
+    }
+
+    /// Render the warning with the offending line of `source` and a caret underline,
+    /// like `EsvError::render`
+    #[must_use]
+    pub fn render(&self, source: &str) -> String {
+        render_snippet(self.to_string(), Some(self.span()), source)
+    }
+}
+
+impl fmt::Display for EsvWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EsvWarning::BareQuote { line, column, .. } => {
+                write!(
+                    f,
+                    "bare quote in unquoted field at line {line}, column {column}"
+                )
+            }
+            EsvWarning::NormalizedLineBreak { line, column, .. } => {
+                write!(
+                    f,
+                    "line break in quoted field at line {line}, column {column} normalized to LF"
+                )
+            }
+            EsvWarning::TrailingEmptyRecord { line, .. } => {
+                write!(f, "empty record at line {line} dropped at end of input")
+            }
+            EsvWarning::MixedLineEndings {
+                line,
+                expected,
+                found,
+                ..
+            } => {
+                write!(
+                    f,
+                    "mixed line endings: line {line} ends with {found}, earlier lines end with {expected}"
+                )
+            }
+        }
+    }
+}
+
+/// Display name of a line break
+pub(crate) fn line_break_name(line_break: &str) -> &'static str {
+    match line_break {
+        "\r\n" => "CRLF",
+        "\r" => "CR",
+        _ => "LF",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warning_display() {
+        let span = Span {
+            start: 2,
+            end: 3,
+            grapheme_column: 3,
+        };
+        let warning = EsvWarning::BareQuote {
+            line: 1,
+            column: 3,
+            span,
+        };
+        assert_eq!(
+            warning.to_string(),
+            "bare quote in unquoted field at line 1, column 3"
+        );
+        assert_eq!(warning.span(), span);
+
+        let warning = EsvWarning::MixedLineEndings {
+            line: 4,
+            expected: "LF",
+            found: "CRLF",
+            span,
+        };
+        assert_eq!(
+            warning.to_string(),
+            "mixed line endings: line 4 ends with CRLF, earlier lines end with LF"
+        );
+    }
+
+    #[test]
+    fn test_warning_render() {
+        let source = "a🔥b\nc🔥d\r\n";
+        let warning = EsvWarning::MixedLineEndings {
+            line: 2,
+            expected: "LF",
+            found: "CRLF",
+            span: Span {
+                start: 13,
+                end: 15,
+                grapheme_column: 4,
+            },
+        };
+        assert_eq!(
+            warning.render(source),
+            "mixed line endings: line 2 ends with CRLF, earlier lines end with LF\n \
+             --> line 2, grapheme column 4, bytes 13..15\n  \
+             |\n\
+             2 | c🔥d\n  \
+             |     ^"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Streaming ESV writer
+//!
+//! Writes ESV headers and records incrementally to any `io::Write`, so large
+//! outputs never have to be built up as a single `String`.
+
+use std::io::Write;
+
+use crate::error::EsvError;
+use crate::EsvSerializer;
+
+/// Streaming writer that serializes ESV records to an `io::Write`
+///
+/// Fields are quoted with the same rules as `EsvSerializer::serialize`, and every
+/// row is terminated with the serializer's `LineEnding`. The separator is validated
+/// against the serializer's `SeparatorPolicy` before anything is written.
+#[derive(Debug)]
+pub struct EsvWriter<W: Write> {
+    writer: W,
+    serializer: EsvSerializer,
+    buffer: String,
+    validated: bool,
+    started: bool,
+}
+
+impl<W: Write> EsvWriter<W> {
+    /// Create a new writer with default serializer settings
+    pub fn new(writer: W) -> Self {
+        Self::from_serializer(EsvSerializer::new(), writer)
+    }
+
+    /// Create a new writer using the settings of an existing serializer
+    pub fn from_serializer(serializer: EsvSerializer, writer: W) -> Self {
+        Self {
+            writer,
+            serializer,
+            buffer: String::new(),
+            validated: false,
+            started: false,
+        }
+    }
+
+    /// Write the header row
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::InvalidSeparator` if the separator policy rejects the separator,
+    /// `EsvError::InvalidQuote` if the quote character is invalid, or `EsvError::Io` if
+    /// writing fails.
+    pub fn write_headers<I, T>(&mut self, headers: I) -> Result<(), EsvError>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.write_row(headers, true)
+    }
+
+    /// Write a single record
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::InvalidSeparator` if the separator policy rejects the separator,
+    /// `EsvError::InvalidQuote` if the quote character is invalid, or `EsvError::Io` if
+    /// writing fails.
+    pub fn write_record<I, T>(&mut self, record: I) -> Result<(), EsvError>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.write_row(record, false)
+    }
+
+    /// Write one row, preceded by the pragma line and comment block if this is the
+    /// first row
+    fn write_row<I, T>(&mut self, row: I, is_header: bool) -> Result<(), EsvError>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        if !self.validated {
+            self.serializer.validate()?;
+            self.validated = true;
+        }
+
+        self.buffer.clear();
+        let first_row = !self.started;
+        if first_row {
+            self.serializer
+                .serialize_preamble(is_header, &mut self.buffer);
+        }
+        self.started = true;
+        self.serializer
+            .serialize_record(row, first_row, &mut self.buffer);
+        self.buffer.push_str(self.serializer.line_ending.as_str());
+        self.writer.write_all(self.buffer.as_bytes())?;
+        Ok(())
+    }
+
+    /// Flush the underlying writer
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::Io` if flushing fails.
+    pub fn flush(&mut self) -> Result<(), EsvError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Flush and consume the writer, returning the underlying `io::Write`
+    ///
+    /// # Errors
+    ///
+    /// Returns `EsvError::Io` if flushing fails.
+    pub fn into_inner(mut self) -> Result<W, EsvError> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::SeparatorRejection;
+    use crate::{EsvDocument, LineEnding};
+
+    fn written(writer: EsvWriter<Vec<u8>>) -> String {
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_writer_headers_and_records() {
+        let mut writer = EsvWriter::new(Vec::new());
+        writer.write_headers(["name", "age"]).unwrap();
+        writer.write_record(["Alice", "30"]).unwrap();
+        writer
+            .write_record(vec!["Bob".to_string(), "25".to_string()])
+            .unwrap();
+        assert_eq!(written(writer), "name🔥age\nAlice🔥30\nBob🔥25\n");
+    }
+
+    #[test]
+    fn test_writer_matches_serializer() {
+        let serializer = EsvSerializer::new()
+            .with_separator('😀')
+            .with_line_ending(LineEnding::Crlf);
+        let doc = EsvDocument::with_headers(
+            vec!["a😀b".to_string(), "c".to_string()],
+            vec![
+                vec!["has \"quotes\"".to_string(), "line\nbreak".to_string()],
+                vec![String::new(), "plain".to_string()],
+            ],
+        );
+
+        let mut writer = EsvWriter::from_serializer(serializer.clone(), Vec::new());
+        writer.write_headers(doc.headers.as_ref().unwrap()).unwrap();
+        for record in &doc.records {
+            writer.write_record(record).unwrap();
+        }
+
+        assert_eq!(written(writer), serializer.serialize(&doc));
+    }
+
+    #[test]
+    fn test_writer_pragma() {
+        let serializer = EsvSerializer::new().with_pragma(true);
+        let mut writer = EsvWriter::from_serializer(serializer.clone(), Vec::new());
+        writer.write_headers(["a"]).unwrap();
+        writer.write_record(["1"]).unwrap();
+        assert_eq!(written(writer), "#!esv sep=🔥 header=true eol=lf\na\n1\n");
+
+        let mut writer = EsvWriter::from_serializer(serializer, Vec::new());
+        writer.write_record(["1"]).unwrap();
+        assert_eq!(written(writer), "#!esv sep=🔥 header=false eol=lf\n1\n");
+    }
+
+    #[test]
+    fn test_writer_comment() {
+        let serializer = EsvSerializer::new().with_pragma(true).with_comment("a\nb");
+        let mut writer = EsvWriter::from_serializer(serializer, Vec::new());
+        writer.write_record(["#1"]).unwrap();
+        writer.write_record(["#2"]).unwrap();
+        assert_eq!(
+            written(writer),
+            "#!esv sep=🔥 header=false eol=lf\n# a\n# b\n\"#1\"\n\"#2\"\n"
+        );
+    }
+
+    #[test]
+    fn test_writer_pragma_like_first_field() {
+        let mut writer = EsvWriter::new(Vec::new());
+        writer.write_record(["#!esv note", "x"]).unwrap();
+        writer.write_record(["#!esv note", "b"]).unwrap();
+        let output = written(writer);
+        assert_eq!(output, "\"#!esv note\"🔥x\n#!esv note🔥b\n");
+        assert_eq!(
+            crate::EsvParser::new().parse(&output).unwrap().records,
+            vec![vec!["#!esv note", "x"], vec!["#!esv note", "b"]]
+        );
+    }
+
+    #[test]
+    fn test_writer_always_quote() {
+        let mut writer =
+            EsvWriter::from_serializer(EsvSerializer::new().with_always_quote(true), Vec::new());
+        writer.write_record(["a", "b"]).unwrap();
+        assert_eq!(written(writer), "\"a\"🔥\"b\"\n");
+    }
+
+    #[test]
+    fn test_writer_rejects_ascii_separator() {
+        let mut writer =
+            EsvWriter::from_serializer(EsvSerializer::new().with_separator(','), Vec::new());
+        let result = writer.write_record(["a", "b"]);
+        assert_eq!(
+            result,
+            Err(EsvError::InvalidSeparator {
+                separator: ",".to_string(),
+                reason: SeparatorRejection::NotEmoji,
+            })
+        );
+        assert_eq!(written(writer), "");
+    }
+
+    #[test]
+    fn test_writer_io_error() {
+        struct FailingWriter;
+
+        impl Write for FailingWriter {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Err(io::Error::other("disk full"))
+            }
+        }
+
+        let mut writer = EsvWriter::new(FailingWriter);
+        assert!(matches!(
+            writer.write_record(["a"]),
+            Err(EsvError::Io {
+                kind: io::ErrorKind::Other,
+                ..
+            })
+        ));
+        assert!(matches!(writer.flush(), Err(EsvError::Io { .. })));
+    }
+}